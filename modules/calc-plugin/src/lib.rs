@@ -1,24 +1,77 @@
-use bindings::exports::swift::launcher::runner::{ActionItem, Guest};
+use bindings::exports::swift::launcher::runner::{Guest, PreviewContent, ResultSink};
+use bindings::swift::launcher::types::{Action, ActionItem};
+use evalexpr::Value;
 mod bindings;
 
 struct Calculator;
 
 impl Guest for Calculator {
+    fn init(_config: String) -> Result<(), String> {
+        Ok(())
+    }
+
     fn get_trigger() -> String {
         "=".to_string()
     }
 
+    fn is_global() -> bool {
+        true
+    }
+
     fn handle(input: String) -> Vec<ActionItem> {
-        let expression = input.trim_start_matches('=');
+        let Some(expression) = input.strip_prefix('=') else {
+            // Global query, only answer when it's actually a calculation
+            return match evalexpr::eval(&input) {
+                Ok(value) if value.to_string() != input.trim() => {
+                    vec![result_item(&input, value.to_string(), Some(1.0))]
+                }
+                _ => Vec::new(),
+            };
+        };
+
         let result = evalexpr::eval(expression)
             .map(|v| v.to_string())
             .unwrap_or_else(|_| "Error".into());
 
-        vec![ActionItem {
-            name: format!("{} = {}", expression, result),
-            exec: format!("echo -n '{}' | wl-copy", result),
-            keywords: "=".into(),
-        }]
+        vec![result_item(expression, result, None)]
+    }
+
+    // A calculation is instant, there is nothing to stream
+    fn handle_streaming(input: String, sink: &ResultSink) {
+        sink.push(&Self::handle(input));
+    }
+
+    fn on_activate(_item_id: String) {}
+
+    // The id is the expression, whole numbers also show up in hex and binary
+    fn preview(item_id: String) -> PreviewContent {
+        let Ok(value) = evalexpr::eval(&item_id) else {
+            return PreviewContent::None;
+        };
+
+        let mut details = vec![
+            ("Expression".to_string(), item_id.trim().to_string()),
+            ("Result".to_string(), value.to_string()),
+        ];
+        if let Value::Int(int) = value {
+            details.push(("Hex".to_string(), format!("{:#x}", int)));
+            details.push(("Binary".to_string(), format!("{:#b}", int)));
+        }
+        PreviewContent::Details(details)
+    }
+
+    fn shutdown() {}
+}
+
+fn result_item(expression: &str, result: String, score: Option<f32>) -> ActionItem {
+    ActionItem {
+        name: format!("{} = {}", expression, result),
+        action: Action::CopyText(result),
+        keywords: "=".into(),
+        description: Some("Copy result to clipboard".into()),
+        icon: Some("accessories-calculator".into()),
+        score,
+        id: Some(expression.to_string()),
     }
 }
 
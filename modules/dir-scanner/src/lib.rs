@@ -1,18 +1,34 @@
-use bindings::exports::swift::launcher::runner::{ActionItem, Guest};
+use bindings::exports::swift::launcher::runner::{Guest, PreviewContent, ResultSink};
+use bindings::swift::launcher::types::{Action, ActionItem};
 mod bindings;
 
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
+// Only the start of a file is previewed, reading a large one would stall the launcher
+const PREVIEW_BYTES: u64 = 4096;
+// Entries per batch when streaming a listing
+const STREAM_BATCH: usize = 100;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp"];
+
 struct DirectoryScanner;
 
 impl Guest for DirectoryScanner {
+    fn init(_config: String) -> Result<(), String> {
+        Ok(())
+    }
+
     fn get_trigger() -> String {
         "/".to_string()
     }
 
+    fn is_global() -> bool {
+        false
+    }
+
     fn handle(input: String) -> Vec<ActionItem> {
         // Trigger as long as we are in "path mode"
         if !input.starts_with('/') {
@@ -49,23 +65,9 @@ impl Guest for DirectoryScanner {
                     // If pattern is empty (e.g. user just typed "/"), show all files
                     // Otherwise, fuzzy match the filename against the pattern
                     if pattern.is_empty() {
-                        results.push((
-                            1,
-                            ActionItem {
-                                name: display_name,
-                                exec: format!("xdg-open {}", full_path_str),
-                                keywords: "/".into(),
-                            },
-                        ));
+                        results.push((1, entry_item(display_name, full_path_str, is_dir)));
                     } else if let Some(score) = matcher.fuzzy_match(&file_name, pattern) {
-                        results.push((
-                            score,
-                            ActionItem {
-                                name: display_name,
-                                exec: format!("xdg-open {}", full_path_str),
-                                keywords: "/".into(),
-                            },
-                        ));
+                        results.push((score, entry_item(display_name, full_path_str, is_dir)));
                     }
                 }
             }
@@ -76,6 +78,100 @@ impl Guest for DirectoryScanner {
 
         results.into_iter().map(|(_, item)| item).collect()
     }
+
+    // A plain listing shows up while the directory is read, a fuzzy search needs every
+    // entry before it can rank them
+    fn handle_streaming(input: String, sink: &ResultSink) {
+        if !input.ends_with('/') {
+            sink.push(&Self::handle(input));
+            return;
+        }
+        let Ok(entries) = fs::read_dir(&input) else {
+            return;
+        };
+
+        let mut batch = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path().to_string_lossy().to_string();
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            batch.push(entry_item(path.clone(), path, is_dir));
+            // False once the user typed on
+            if batch.len() == STREAM_BATCH && !sink.push(&std::mem::take(&mut batch)) {
+                return;
+            }
+        }
+        sink.push(&batch);
+    }
+
+    fn on_activate(_item_id: String) {}
+
+    // The id is the full path of the entry
+    fn preview(item_id: String) -> PreviewContent {
+        let path = Path::new(&item_id);
+        let Ok(meta) = fs::metadata(path) else {
+            return PreviewContent::None;
+        };
+
+        if meta.is_dir() {
+            let entries = fs::read_dir(path)
+                .map(|entries| entries.count())
+                .unwrap_or(0);
+            return PreviewContent::Details(vec![
+                ("Type".to_string(), "Directory".to_string()),
+                ("Entries".to_string(), entries.to_string()),
+            ]);
+        }
+
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            return PreviewContent::Image(item_id);
+        }
+
+        let mut head = Vec::new();
+        let read =
+            fs::File::open(path).and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut head));
+        // A NUL byte means it's not text
+        if read.is_ok() && !head.is_empty() && !head.contains(&0) {
+            return PreviewContent::Text(String::from_utf8_lossy(&head).into_owned());
+        }
+
+        PreviewContent::Details(vec![
+            ("Type".to_string(), "File".to_string()),
+            ("Size".to_string(), format_size(meta.len())),
+        ])
+    }
+
+    fn shutdown() {}
+}
+
+fn entry_item(name: String, path: String, is_dir: bool) -> ActionItem {
+    ActionItem {
+        name,
+        action: Action::Spawn(vec!["xdg-open".into(), path.clone()]),
+        keywords: "/".into(),
+        description: None,
+        icon: Some(if is_dir { "folder" } else { "text-x-generic" }.into()),
+        score: None,
+        id: Some(path),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
 }
 
 bindings::export!(DirectoryScanner with_types_in bindings);
@@ -3,6 +3,451 @@
 //   * runtime_path: "wit_bindgen_rt"
 #[rustfmt::skip]
 #[allow(dead_code, clippy::all)]
+pub mod swift {
+    pub mod launcher {
+        /// Implemented by the launcher, so plugins don't need wl-copy or a display connection
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod clipboard {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            #[allow(unused_unsafe, clippy::all)]
+            /// Replaces the clipboard contents, they stay available after the launcher closes
+            pub fn set_text(text: &str) -> Result<(), _rt::String> {
+                unsafe {
+                    #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
+                    #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
+                    struct RetArea(
+                        [::core::mem::MaybeUninit<
+                            u8,
+                        >; 3 * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let mut ret_area = RetArea(
+                        [::core::mem::MaybeUninit::uninit(); 3
+                            * ::core::mem::size_of::<*const u8>()],
+                    );
+                    let vec0 = text;
+                    let ptr0 = vec0.as_ptr().cast::<u8>();
+                    let len0 = vec0.len();
+                    let ptr1 = ret_area.0.as_mut_ptr().cast::<u8>();
+                    #[cfg(target_arch = "wasm32")]
+                    #[link(wasm_import_module = "swift:launcher/clipboard@0.2.0")]
+                    unsafe extern "C" {
+                        #[link_name = "set-text"]
+                        fn wit_import2(_: *mut u8, _: usize, _: *mut u8);
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unsafe extern "C" fn wit_import2(_: *mut u8, _: usize, _: *mut u8) {
+                        unreachable!()
+                    }
+                    unsafe { wit_import2(ptr0.cast_mut(), len0, ptr1) };
+                    let l3 = i32::from(*ptr1.add(0).cast::<u8>());
+                    let result7 = match l3 {
+                        0 => {
+                            let e = ();
+                            Ok(e)
+                        }
+                        1 => {
+                            let e = {
+                                let l4 = *ptr1
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>();
+                                let l5 = *ptr1
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>();
+                                let len6 = l5;
+                                let bytes6 = _rt::Vec::from_raw_parts(
+                                    l4.cast(),
+                                    len6,
+                                    len6,
+                                );
+                                _rt::string_lift(bytes6)
+                            };
+                            Err(e)
+                        }
+                        _ => _rt::invalid_enum_discriminant(),
+                    };
+                    result7
+                }
+            }
+        }
+        /// Shared by what plugins return and what they stream
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod types {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            /// What happens when the user picks an item, carried out by the launcher itself
+            #[derive(Clone)]
+            pub enum Action {
+                /// Program and its arguments, started without a shell
+                Spawn(_rt::Vec<_rt::String>),
+                /// Opened with the user's default handler
+                OpenUri(_rt::String),
+                /// Put on the clipboard
+                CopyText(_rt::String),
+                /// Run through `sh -c`
+                Shell(_rt::String),
+                /// Informational row, selecting it does nothing
+                None,
+            }
+            impl ::core::fmt::Debug for Action {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    match self {
+                        Action::Spawn(e) => {
+                            f.debug_tuple("Action::Spawn").field(e).finish()
+                        }
+                        Action::OpenUri(e) => {
+                            f.debug_tuple("Action::OpenUri").field(e).finish()
+                        }
+                        Action::CopyText(e) => {
+                            f.debug_tuple("Action::CopyText").field(e).finish()
+                        }
+                        Action::Shell(e) => {
+                            f.debug_tuple("Action::Shell").field(e).finish()
+                        }
+                        Action::None => f.debug_tuple("Action::None").finish(),
+                    }
+                }
+            }
+            #[derive(Clone)]
+            pub struct ActionItem {
+                pub name: _rt::String,
+                pub action: Action,
+                pub keywords: _rt::String,
+                /// Secondary line shown under the name
+                pub description: Option<_rt::String>,
+                /// Icon theme name or absolute path
+                pub icon: Option<_rt::String>,
+                /// Higher ranks first, unscored items keep the order they were returned in.
+                /// Results of a global query are mixed with apps, 1.0 ranks like a good name match
+                pub score: Option<f32>,
+                /// Handed back to on-activate when the user picks the item, and to preview while it's selected
+                pub id: Option<_rt::String>,
+            }
+            impl ::core::fmt::Debug for ActionItem {
+                fn fmt(
+                    &self,
+                    f: &mut ::core::fmt::Formatter<'_>,
+                ) -> ::core::fmt::Result {
+                    f.debug_struct("ActionItem")
+                        .field("name", &self.name)
+                        .field("action", &self.action)
+                        .field("keywords", &self.keywords)
+                        .field("description", &self.description)
+                        .field("icon", &self.icon)
+                        .field("score", &self.score)
+                        .field("id", &self.id)
+                        .finish()
+                }
+            }
+        }
+        /// Implemented by the launcher, lets a plugin show results while it's still looking for more
+        #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
+        pub mod results {
+            #[used]
+            #[doc(hidden)]
+            static __FORCE_SECTION_REF: fn() = super::super::super::__link_custom_section_describing_imports;
+            use super::super::super::_rt;
+            pub type ActionItem = super::super::super::swift::launcher::types::ActionItem;
+            /// Where the results of one streaming query go
+            #[derive(Debug)]
+            #[repr(transparent)]
+            pub struct ResultSink {
+                handle: _rt::Resource<ResultSink>,
+            }
+            impl ResultSink {
+                #[doc(hidden)]
+                pub unsafe fn from_handle(handle: u32) -> Self {
+                    Self {
+                        handle: unsafe { _rt::Resource::from_handle(handle) },
+                    }
+                }
+                #[doc(hidden)]
+                pub fn take_handle(&self) -> u32 {
+                    _rt::Resource::take_handle(&self.handle)
+                }
+                #[doc(hidden)]
+                pub fn handle(&self) -> u32 {
+                    _rt::Resource::handle(&self.handle)
+                }
+            }
+            unsafe impl _rt::WasmResource for ResultSink {
+                #[inline]
+                unsafe fn drop(_handle: u32) {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    unreachable!();
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        #[link(wasm_import_module = "swift:launcher/results@0.2.0")]
+                        unsafe extern "C" {
+                            #[link_name = "[resource-drop]result-sink"]
+                            fn drop(_: u32);
+                        }
+                        unsafe { drop(_handle) };
+                    }
+                }
+            }
+            impl ResultSink {
+                #[allow(unused_unsafe, clippy::all)]
+                /// Appends the items below the ones pushed before. Returns false once the user typed on,
+                /// the plugin should stop working on the query then
+                pub fn push(&self, items: &[ActionItem]) -> bool {
+                    unsafe {
+                        let mut cleanup_list = _rt::Vec::new();
+                        let vec12 = items;
+                        let len12 = vec12.len();
+                        let layout12 = _rt::alloc::Layout::from_size_align_unchecked(
+                            vec12.len() * (8 + 16 * ::core::mem::size_of::<*const u8>()),
+                            ::core::mem::size_of::<*const u8>(),
+                        );
+                        let result12 = if layout12.size() != 0 {
+                            let ptr = _rt::alloc::alloc(layout12).cast::<u8>();
+                            if ptr.is_null() {
+                                _rt::alloc::handle_alloc_error(layout12);
+                            }
+                            ptr
+                        } else {
+                            ::core::ptr::null_mut()
+                        };
+                        for (i, e) in vec12.into_iter().enumerate() {
+                            let base = result12
+                                .add(i * (8 + 16 * ::core::mem::size_of::<*const u8>()));
+                            {
+                                let super::super::super::swift::launcher::types::ActionItem {
+                                    name: name0,
+                                    action: action0,
+                                    keywords: keywords0,
+                                    description: description0,
+                                    icon: icon0,
+                                    score: score0,
+                                    id: id0,
+                                } = e;
+                                let vec1 = name0;
+                                let ptr1 = vec1.as_ptr().cast::<u8>();
+                                let len1 = vec1.len();
+                                *base
+                                    .add(::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>() = len1;
+                                *base.add(0).cast::<*mut u8>() = ptr1.cast_mut();
+                                use super::super::super::swift::launcher::types::Action as V7;
+                                match action0 {
+                                    V7::Spawn(e) => {
+                                        *base
+                                            .add(2 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (0i32) as u8;
+                                        let vec3 = e;
+                                        let len3 = vec3.len();
+                                        let layout3 = _rt::alloc::Layout::from_size_align_unchecked(
+                                            vec3.len() * (2 * ::core::mem::size_of::<*const u8>()),
+                                            ::core::mem::size_of::<*const u8>(),
+                                        );
+                                        let result3 = if layout3.size() != 0 {
+                                            let ptr = _rt::alloc::alloc(layout3).cast::<u8>();
+                                            if ptr.is_null() {
+                                                _rt::alloc::handle_alloc_error(layout3);
+                                            }
+                                            ptr
+                                        } else {
+                                            ::core::ptr::null_mut()
+                                        };
+                                        for (i, e) in vec3.into_iter().enumerate() {
+                                            let base = result3
+                                                .add(i * (2 * ::core::mem::size_of::<*const u8>()));
+                                            {
+                                                let vec2 = e;
+                                                let ptr2 = vec2.as_ptr().cast::<u8>();
+                                                let len2 = vec2.len();
+                                                *base
+                                                    .add(::core::mem::size_of::<*const u8>())
+                                                    .cast::<usize>() = len2;
+                                                *base.add(0).cast::<*mut u8>() = ptr2.cast_mut();
+                                            }
+                                        }
+                                        *base
+                                            .add(4 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len3;
+                                        *base
+                                            .add(3 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = result3;
+                                        cleanup_list.extend_from_slice(&[(result3, layout3)]);
+                                    }
+                                    V7::OpenUri(e) => {
+                                        *base
+                                            .add(2 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (1i32) as u8;
+                                        let vec4 = e;
+                                        let ptr4 = vec4.as_ptr().cast::<u8>();
+                                        let len4 = vec4.len();
+                                        *base
+                                            .add(4 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len4;
+                                        *base
+                                            .add(3 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr4.cast_mut();
+                                    }
+                                    V7::CopyText(e) => {
+                                        *base
+                                            .add(2 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (2i32) as u8;
+                                        let vec5 = e;
+                                        let ptr5 = vec5.as_ptr().cast::<u8>();
+                                        let len5 = vec5.len();
+                                        *base
+                                            .add(4 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len5;
+                                        *base
+                                            .add(3 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr5.cast_mut();
+                                    }
+                                    V7::Shell(e) => {
+                                        *base
+                                            .add(2 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (3i32) as u8;
+                                        let vec6 = e;
+                                        let ptr6 = vec6.as_ptr().cast::<u8>();
+                                        let len6 = vec6.len();
+                                        *base
+                                            .add(4 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len6;
+                                        *base
+                                            .add(3 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr6.cast_mut();
+                                    }
+                                    V7::None => {
+                                        *base
+                                            .add(2 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (4i32) as u8;
+                                    }
+                                }
+                                let vec8 = keywords0;
+                                let ptr8 = vec8.as_ptr().cast::<u8>();
+                                let len8 = vec8.len();
+                                *base
+                                    .add(6 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<usize>() = len8;
+                                *base
+                                    .add(5 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<*mut u8>() = ptr8.cast_mut();
+                                match description0 {
+                                    Some(e) => {
+                                        *base
+                                            .add(7 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (1i32) as u8;
+                                        let vec9 = e;
+                                        let ptr9 = vec9.as_ptr().cast::<u8>();
+                                        let len9 = vec9.len();
+                                        *base
+                                            .add(9 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len9;
+                                        *base
+                                            .add(8 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr9.cast_mut();
+                                    }
+                                    None => {
+                                        *base
+                                            .add(7 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (0i32) as u8;
+                                    }
+                                };
+                                match icon0 {
+                                    Some(e) => {
+                                        *base
+                                            .add(10 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (1i32) as u8;
+                                        let vec10 = e;
+                                        let ptr10 = vec10.as_ptr().cast::<u8>();
+                                        let len10 = vec10.len();
+                                        *base
+                                            .add(12 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len10;
+                                        *base
+                                            .add(11 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr10.cast_mut();
+                                    }
+                                    None => {
+                                        *base
+                                            .add(10 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (0i32) as u8;
+                                    }
+                                };
+                                match score0 {
+                                    Some(e) => {
+                                        *base
+                                            .add(13 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (1i32) as u8;
+                                        *base
+                                            .add(4 + 13 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<f32>() = _rt::as_f32(e);
+                                    }
+                                    None => {
+                                        *base
+                                            .add(13 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (0i32) as u8;
+                                    }
+                                };
+                                match id0 {
+                                    Some(e) => {
+                                        *base
+                                            .add(8 + 13 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (1i32) as u8;
+                                        let vec11 = e;
+                                        let ptr11 = vec11.as_ptr().cast::<u8>();
+                                        let len11 = vec11.len();
+                                        *base
+                                            .add(8 + 15 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<usize>() = len11;
+                                        *base
+                                            .add(8 + 14 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<*mut u8>() = ptr11.cast_mut();
+                                    }
+                                    None => {
+                                        *base
+                                            .add(8 + 13 * ::core::mem::size_of::<*const u8>())
+                                            .cast::<u8>() = (0i32) as u8;
+                                    }
+                                };
+                            }
+                        }
+                        #[cfg(target_arch = "wasm32")]
+                        #[link(wasm_import_module = "swift:launcher/results@0.2.0")]
+                        unsafe extern "C" {
+                            #[link_name = "[method]result-sink.push"]
+                            fn wit_import13(_: i32, _: *mut u8, _: usize) -> i32;
+                        }
+                        #[cfg(not(target_arch = "wasm32"))]
+                        unsafe extern "C" fn wit_import13(
+                            _: i32,
+                            _: *mut u8,
+                            _: usize,
+                        ) -> i32 {
+                            unreachable!()
+                        }
+                        let ret = unsafe {
+                            wit_import13((self).handle() as i32, result12, len12)
+                        };
+                        if layout12.size() != 0 {
+                            _rt::alloc::dealloc(result12.cast(), layout12);
+                        }
+                        for (ptr, layout) in cleanup_list {
+                            if layout.size() != 0 {
+                                _rt::alloc::dealloc(ptr.cast(), layout);
+                            }
+                        }
+                        _rt::bool_lift(ret as u8)
+                    }
+                }
+            }
+        }
+    }
+}
+#[rustfmt::skip]
+#[allow(dead_code, clippy::all)]
 pub mod exports {
     pub mod swift {
         pub mod launcher {
@@ -12,22 +457,87 @@ pub mod exports {
                 #[doc(hidden)]
                 static __FORCE_SECTION_REF: fn() = super::super::super::super::__link_custom_section_describing_imports;
                 use super::super::super::super::_rt;
+                pub type ActionItem = super::super::super::super::swift::launcher::types::ActionItem;
+                pub type ResultSink = super::super::super::super::swift::launcher::results::ResultSink;
+                /// Shown in a pane next to the results while its item is selected
                 #[derive(Clone)]
-                pub struct ActionItem {
-                    pub name: _rt::String,
-                    pub exec: _rt::String,
-                    pub keywords: _rt::String,
+                pub enum PreviewContent {
+                    /// Plain text such as the start of a file
+                    Text(_rt::String),
+                    /// Path of an image inside a directory the plugin may read, scaled to fit
+                    Image(_rt::String),
+                    /// Label and value rows, e.g. the size and type of a file
+                    Details(_rt::Vec<(_rt::String, _rt::String)>),
+                    /// Nothing worth showing, the pane stays closed
+                    None,
                 }
-                impl ::core::fmt::Debug for ActionItem {
+                impl ::core::fmt::Debug for PreviewContent {
                     fn fmt(
                         &self,
                         f: &mut ::core::fmt::Formatter<'_>,
                     ) -> ::core::fmt::Result {
-                        f.debug_struct("ActionItem")
-                            .field("name", &self.name)
-                            .field("exec", &self.exec)
-                            .field("keywords", &self.keywords)
-                            .finish()
+                        match self {
+                            PreviewContent::Text(e) => {
+                                f.debug_tuple("PreviewContent::Text").field(e).finish()
+                            }
+                            PreviewContent::Image(e) => {
+                                f.debug_tuple("PreviewContent::Image").field(e).finish()
+                            }
+                            PreviewContent::Details(e) => {
+                                f.debug_tuple("PreviewContent::Details").field(e).finish()
+                            }
+                            PreviewContent::None => {
+                                f.debug_tuple("PreviewContent::None").finish()
+                            }
+                        }
+                    }
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_init_cabi<T: Guest>(
+                    arg0: *mut u8,
+                    arg1: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len0 = arg1;
+                    let bytes0 = _rt::Vec::from_raw_parts(arg0.cast(), len0, len0);
+                    let result1 = T::init(_rt::string_lift(bytes0));
+                    let ptr2 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result1 {
+                        Ok(_) => {
+                            *ptr2.add(0).cast::<u8>() = (0i32) as u8;
+                        }
+                        Err(e) => {
+                            *ptr2.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec3 = (e.into_bytes()).into_boxed_slice();
+                            let ptr3 = vec3.as_ptr().cast::<u8>();
+                            let len3 = vec3.len();
+                            ::core::mem::forget(vec3);
+                            *ptr2
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len3;
+                            *ptr2
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr3.cast_mut();
+                        }
+                    };
+                    ptr2
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_init<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {}
+                        _ => {
+                            let l1 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l2 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l1, l2, 1);
+                        }
                     }
                 }
                 #[doc(hidden)]
@@ -55,6 +565,16 @@ pub mod exports {
                 }
                 #[doc(hidden)]
                 #[allow(non_snake_case)]
+                pub unsafe fn _export_is_global_cabi<T: Guest>() -> i32 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let result0 = T::is_global();
+                    match result0 {
+                        true => 1,
+                        false => 0,
+                    }
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
                 pub unsafe fn _export_handle_cabi<T: Guest>(
                     arg0: *mut u8,
                     arg1: usize,
@@ -64,29 +584,33 @@ pub mod exports {
                     let bytes0 = _rt::Vec::from_raw_parts(arg0.cast(), len0, len0);
                     let result1 = T::handle(_rt::string_lift(bytes0));
                     let ptr2 = (&raw mut _RET_AREA.0).cast::<u8>();
-                    let vec7 = result1;
-                    let len7 = vec7.len();
-                    let layout7 = _rt::alloc::Layout::from_size_align_unchecked(
-                        vec7.len() * (6 * ::core::mem::size_of::<*const u8>()),
+                    let vec15 = result1;
+                    let len15 = vec15.len();
+                    let layout15 = _rt::alloc::Layout::from_size_align_unchecked(
+                        vec15.len() * (8 + 16 * ::core::mem::size_of::<*const u8>()),
                         ::core::mem::size_of::<*const u8>(),
                     );
-                    let result7 = if layout7.size() != 0 {
-                        let ptr = _rt::alloc::alloc(layout7).cast::<u8>();
+                    let result15 = if layout15.size() != 0 {
+                        let ptr = _rt::alloc::alloc(layout15).cast::<u8>();
                         if ptr.is_null() {
-                            _rt::alloc::handle_alloc_error(layout7);
+                            _rt::alloc::handle_alloc_error(layout15);
                         }
                         ptr
                     } else {
                         ::core::ptr::null_mut()
                     };
-                    for (i, e) in vec7.into_iter().enumerate() {
-                        let base = result7
-                            .add(i * (6 * ::core::mem::size_of::<*const u8>()));
+                    for (i, e) in vec15.into_iter().enumerate() {
+                        let base = result15
+                            .add(i * (8 + 16 * ::core::mem::size_of::<*const u8>()));
                         {
-                            let ActionItem {
+                            let super::super::super::super::swift::launcher::types::ActionItem {
                                 name: name3,
-                                exec: exec3,
+                                action: action3,
                                 keywords: keywords3,
+                                description: description3,
+                                icon: icon3,
+                                score: score3,
+                                id: id3,
                             } = e;
                             let vec4 = (name3.into_bytes()).into_boxed_slice();
                             let ptr4 = vec4.as_ptr().cast::<u8>();
@@ -96,30 +620,194 @@ pub mod exports {
                                 .add(::core::mem::size_of::<*const u8>())
                                 .cast::<usize>() = len4;
                             *base.add(0).cast::<*mut u8>() = ptr4.cast_mut();
-                            let vec5 = (exec3.into_bytes()).into_boxed_slice();
-                            let ptr5 = vec5.as_ptr().cast::<u8>();
-                            let len5 = vec5.len();
-                            ::core::mem::forget(vec5);
+                            use super::super::super::super::swift::launcher::types::Action as V10;
+                            match action3 {
+                                V10::Spawn(e) => {
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                    let vec6 = e;
+                                    let len6 = vec6.len();
+                                    let layout6 = _rt::alloc::Layout::from_size_align_unchecked(
+                                        vec6.len() * (2 * ::core::mem::size_of::<*const u8>()),
+                                        ::core::mem::size_of::<*const u8>(),
+                                    );
+                                    let result6 = if layout6.size() != 0 {
+                                        let ptr = _rt::alloc::alloc(layout6).cast::<u8>();
+                                        if ptr.is_null() {
+                                            _rt::alloc::handle_alloc_error(layout6);
+                                        }
+                                        ptr
+                                    } else {
+                                        ::core::ptr::null_mut()
+                                    };
+                                    for (i, e) in vec6.into_iter().enumerate() {
+                                        let base = result6
+                                            .add(i * (2 * ::core::mem::size_of::<*const u8>()));
+                                        {
+                                            let vec5 = (e.into_bytes()).into_boxed_slice();
+                                            let ptr5 = vec5.as_ptr().cast::<u8>();
+                                            let len5 = vec5.len();
+                                            ::core::mem::forget(vec5);
+                                            *base
+                                                .add(::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>() = len5;
+                                            *base.add(0).cast::<*mut u8>() = ptr5.cast_mut();
+                                        }
+                                    }
+                                    *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len6;
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = result6;
+                                }
+                                V10::OpenUri(e) => {
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec7 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr7 = vec7.as_ptr().cast::<u8>();
+                                    let len7 = vec7.len();
+                                    ::core::mem::forget(vec7);
+                                    *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len7;
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr7.cast_mut();
+                                }
+                                V10::CopyText(e) => {
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (2i32) as u8;
+                                    let vec8 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr8 = vec8.as_ptr().cast::<u8>();
+                                    let len8 = vec8.len();
+                                    ::core::mem::forget(vec8);
+                                    *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len8;
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr8.cast_mut();
+                                }
+                                V10::Shell(e) => {
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (3i32) as u8;
+                                    let vec9 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr9 = vec9.as_ptr().cast::<u8>();
+                                    let len9 = vec9.len();
+                                    ::core::mem::forget(vec9);
+                                    *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len9;
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr9.cast_mut();
+                                }
+                                V10::None => {
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (4i32) as u8;
+                                }
+                            }
+                            let vec11 = (keywords3.into_bytes()).into_boxed_slice();
+                            let ptr11 = vec11.as_ptr().cast::<u8>();
+                            let len11 = vec11.len();
+                            ::core::mem::forget(vec11);
                             *base
-                                .add(3 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len5;
-                            *base
-                                .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr5.cast_mut();
-                            let vec6 = (keywords3.into_bytes()).into_boxed_slice();
-                            let ptr6 = vec6.as_ptr().cast::<u8>();
-                            let len6 = vec6.len();
-                            ::core::mem::forget(vec6);
+                                .add(6 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len11;
                             *base
                                 .add(5 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>() = len6;
-                            *base
-                                .add(4 * ::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>() = ptr6.cast_mut();
+                                .cast::<*mut u8>() = ptr11.cast_mut();
+                            match description3 {
+                                Some(e) => {
+                                    *base
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec12 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr12 = vec12.as_ptr().cast::<u8>();
+                                    let len12 = vec12.len();
+                                    ::core::mem::forget(vec12);
+                                    *base
+                                        .add(9 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len12;
+                                    *base
+                                        .add(8 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr12.cast_mut();
+                                }
+                                None => {
+                                    *base
+                                        .add(7 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            match icon3 {
+                                Some(e) => {
+                                    *base
+                                        .add(10 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec13 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr13 = vec13.as_ptr().cast::<u8>();
+                                    let len13 = vec13.len();
+                                    ::core::mem::forget(vec13);
+                                    *base
+                                        .add(12 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len13;
+                                    *base
+                                        .add(11 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr13.cast_mut();
+                                }
+                                None => {
+                                    *base
+                                        .add(10 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            match score3 {
+                                Some(e) => {
+                                    *base
+                                        .add(13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    *base
+                                        .add(4 + 13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<f32>() = _rt::as_f32(e);
+                                }
+                                None => {
+                                    *base
+                                        .add(13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
+                            match id3 {
+                                Some(e) => {
+                                    *base
+                                        .add(8 + 13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (1i32) as u8;
+                                    let vec14 = (e.into_bytes()).into_boxed_slice();
+                                    let ptr14 = vec14.as_ptr().cast::<u8>();
+                                    let len14 = vec14.len();
+                                    ::core::mem::forget(vec14);
+                                    *base
+                                        .add(8 + 15 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len14;
+                                    *base
+                                        .add(8 + 14 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr14.cast_mut();
+                                }
+                                None => {
+                                    *base
+                                        .add(8 + 13 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<u8>() = (0i32) as u8;
+                                }
+                            };
                         }
                     }
-                    *ptr2.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len7;
-                    *ptr2.add(0).cast::<*mut u8>() = result7;
+                    *ptr2.add(::core::mem::size_of::<*const u8>()).cast::<usize>() = len15;
+                    *ptr2.add(0).cast::<*mut u8>() = result15;
                     ptr2
                 }
                 #[doc(hidden)]
@@ -129,73 +817,419 @@ pub mod exports {
                     let l1 = *arg0
                         .add(::core::mem::size_of::<*const u8>())
                         .cast::<usize>();
-                    let base8 = l0;
-                    let len8 = l1;
-                    for i in 0..len8 {
-                        let base = base8
-                            .add(i * (6 * ::core::mem::size_of::<*const u8>()));
+                    let base27 = l0;
+                    let len27 = l1;
+                    for i in 0..len27 {
+                        let base = base27
+                            .add(i * (8 + 16 * ::core::mem::size_of::<*const u8>()));
                         {
                             let l2 = *base.add(0).cast::<*mut u8>();
                             let l3 = *base
                                 .add(::core::mem::size_of::<*const u8>())
                                 .cast::<usize>();
                             _rt::cabi_dealloc(l2, l3, 1);
-                            let l4 = *base
-                                .add(2 * ::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>();
-                            let l5 = *base
-                                .add(3 * ::core::mem::size_of::<*const u8>())
-                                .cast::<usize>();
-                            _rt::cabi_dealloc(l4, l5, 1);
-                            let l6 = *base
-                                .add(4 * ::core::mem::size_of::<*const u8>())
-                                .cast::<*mut u8>();
-                            let l7 = *base
+                            let l4 = i32::from(
+                                *base
+                                    .add(2 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l4 {
+                                0 => {
+                                    let l5 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l6 = *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    let base9 = l5;
+                                    let len9 = l6;
+                                    for i in 0..len9 {
+                                        let base = base9
+                                            .add(i * (2 * ::core::mem::size_of::<*const u8>()));
+                                        {
+                                            let l7 = *base.add(0).cast::<*mut u8>();
+                                            let l8 = *base
+                                                .add(::core::mem::size_of::<*const u8>())
+                                                .cast::<usize>();
+                                            _rt::cabi_dealloc(l7, l8, 1);
+                                        }
+                                    }
+                                    _rt::cabi_dealloc(
+                                        base9,
+                                        len9 * (2 * ::core::mem::size_of::<*const u8>()),
+                                        ::core::mem::size_of::<*const u8>(),
+                                    );
+                                }
+                                1 => {
+                                    let l10 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l11 = *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l10, l11, 1);
+                                }
+                                2 => {
+                                    let l12 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l13 = *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l12, l13, 1);
+                                }
+                                3 => {
+                                    let l14 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l15 = *base
+                                        .add(4 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l14, l15, 1);
+                                }
+                                _ => {}
+                            }
+                            let l16 = *base
                                 .add(5 * ::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l17 = *base
+                                .add(6 * ::core::mem::size_of::<*const u8>())
                                 .cast::<usize>();
-                            _rt::cabi_dealloc(l6, l7, 1);
+                            _rt::cabi_dealloc(l16, l17, 1);
+                            let l18 = i32::from(
+                                *base
+                                    .add(7 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l18 {
+                                0 => {}
+                                _ => {
+                                    let l19 = *base
+                                        .add(8 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l20 = *base
+                                        .add(9 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l19, l20, 1);
+                                }
+                            }
+                            let l21 = i32::from(
+                                *base
+                                    .add(10 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l21 {
+                                0 => {}
+                                _ => {
+                                    let l22 = *base
+                                        .add(11 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l23 = *base
+                                        .add(12 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l22, l23, 1);
+                                }
+                            }
+                            let l24 = i32::from(
+                                *base
+                                    .add(8 + 13 * ::core::mem::size_of::<*const u8>())
+                                    .cast::<u8>(),
+                            );
+                            match l24 {
+                                0 => {}
+                                _ => {
+                                    let l25 = *base
+                                        .add(8 + 14 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l26 = *base
+                                        .add(8 + 15 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l25, l26, 1);
+                                }
+                            }
                         }
                     }
                     _rt::cabi_dealloc(
-                        base8,
-                        len8 * (6 * ::core::mem::size_of::<*const u8>()),
+                        base27,
+                        len27 * (8 + 16 * ::core::mem::size_of::<*const u8>()),
                         ::core::mem::size_of::<*const u8>(),
                     );
                 }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_handle_streaming_cabi<T: Guest>(
+                    arg0: *mut u8,
+                    arg1: usize,
+                    arg2: i32,
+                ) {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let handle1;
+                    let len0 = arg1;
+                    let bytes0 = _rt::Vec::from_raw_parts(arg0.cast(), len0, len0);
+                    T::handle_streaming(
+                        _rt::string_lift(bytes0),
+                        {
+                            handle1 = unsafe {
+                                super::super::super::super::swift::launcher::results::ResultSink::from_handle(
+                                    arg2 as u32,
+                                )
+                            };
+                            &handle1
+                        },
+                    );
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_on_activate_cabi<T: Guest>(
+                    arg0: *mut u8,
+                    arg1: usize,
+                ) {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len0 = arg1;
+                    let bytes0 = _rt::Vec::from_raw_parts(arg0.cast(), len0, len0);
+                    T::on_activate(_rt::string_lift(bytes0));
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_preview_cabi<T: Guest>(
+                    arg0: *mut u8,
+                    arg1: usize,
+                ) -> *mut u8 {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    let len0 = arg1;
+                    let bytes0 = _rt::Vec::from_raw_parts(arg0.cast(), len0, len0);
+                    let result1 = T::preview(_rt::string_lift(bytes0));
+                    let ptr2 = (&raw mut _RET_AREA.0).cast::<u8>();
+                    match result1 {
+                        PreviewContent::Text(e) => {
+                            *ptr2.add(0).cast::<u8>() = (0i32) as u8;
+                            let vec3 = (e.into_bytes()).into_boxed_slice();
+                            let ptr3 = vec3.as_ptr().cast::<u8>();
+                            let len3 = vec3.len();
+                            ::core::mem::forget(vec3);
+                            *ptr2
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len3;
+                            *ptr2
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr3.cast_mut();
+                        }
+                        PreviewContent::Image(e) => {
+                            *ptr2.add(0).cast::<u8>() = (1i32) as u8;
+                            let vec4 = (e.into_bytes()).into_boxed_slice();
+                            let ptr4 = vec4.as_ptr().cast::<u8>();
+                            let len4 = vec4.len();
+                            ::core::mem::forget(vec4);
+                            *ptr2
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len4;
+                            *ptr2
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = ptr4.cast_mut();
+                        }
+                        PreviewContent::Details(e) => {
+                            *ptr2.add(0).cast::<u8>() = (2i32) as u8;
+                            let vec8 = e;
+                            let len8 = vec8.len();
+                            let layout8 = _rt::alloc::Layout::from_size_align_unchecked(
+                                vec8.len() * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                            let result8 = if layout8.size() != 0 {
+                                let ptr = _rt::alloc::alloc(layout8).cast::<u8>();
+                                if ptr.is_null() {
+                                    _rt::alloc::handle_alloc_error(layout8);
+                                }
+                                ptr
+                            } else {
+                                ::core::ptr::null_mut()
+                            };
+                            for (i, e) in vec8.into_iter().enumerate() {
+                                let base = result8
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let (t5_0, t5_1) = e;
+                                    let vec6 = (t5_0.into_bytes()).into_boxed_slice();
+                                    let ptr6 = vec6.as_ptr().cast::<u8>();
+                                    let len6 = vec6.len();
+                                    ::core::mem::forget(vec6);
+                                    *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len6;
+                                    *base.add(0).cast::<*mut u8>() = ptr6.cast_mut();
+                                    let vec7 = (t5_1.into_bytes()).into_boxed_slice();
+                                    let ptr7 = vec7.as_ptr().cast::<u8>();
+                                    let len7 = vec7.len();
+                                    ::core::mem::forget(vec7);
+                                    *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>() = len7;
+                                    *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>() = ptr7.cast_mut();
+                                }
+                            }
+                            *ptr2
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>() = len8;
+                            *ptr2
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>() = result8;
+                        }
+                        PreviewContent::None => {
+                            *ptr2.add(0).cast::<u8>() = (3i32) as u8;
+                        }
+                    }
+                    ptr2
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn __post_return_preview<T: Guest>(arg0: *mut u8) {
+                    let l0 = i32::from(*arg0.add(0).cast::<u8>());
+                    match l0 {
+                        0 => {
+                            let l1 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l2 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l1, l2, 1);
+                        }
+                        1 => {
+                            let l3 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l4 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            _rt::cabi_dealloc(l3, l4, 1);
+                        }
+                        2 => {
+                            let l5 = *arg0
+                                .add(::core::mem::size_of::<*const u8>())
+                                .cast::<*mut u8>();
+                            let l6 = *arg0
+                                .add(2 * ::core::mem::size_of::<*const u8>())
+                                .cast::<usize>();
+                            let base11 = l5;
+                            let len11 = l6;
+                            for i in 0..len11 {
+                                let base = base11
+                                    .add(i * (4 * ::core::mem::size_of::<*const u8>()));
+                                {
+                                    let l7 = *base.add(0).cast::<*mut u8>();
+                                    let l8 = *base
+                                        .add(::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l7, l8, 1);
+                                    let l9 = *base
+                                        .add(2 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<*mut u8>();
+                                    let l10 = *base
+                                        .add(3 * ::core::mem::size_of::<*const u8>())
+                                        .cast::<usize>();
+                                    _rt::cabi_dealloc(l9, l10, 1);
+                                }
+                            }
+                            _rt::cabi_dealloc(
+                                base11,
+                                len11 * (4 * ::core::mem::size_of::<*const u8>()),
+                                ::core::mem::size_of::<*const u8>(),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                #[doc(hidden)]
+                #[allow(non_snake_case)]
+                pub unsafe fn _export_shutdown_cabi<T: Guest>() {
+                    #[cfg(target_arch = "wasm32")] _rt::run_ctors_once();
+                    T::shutdown();
+                }
                 pub trait Guest {
+                    /// Called once per instance before anything else, with the plugin's own `[plugin:<id>]`
+                    /// section of config.conf as ini lines. An error stops the instance
+                    fn init(config: _rt::String) -> Result<(), _rt::String>;
+                    /// Queries starting with it go to this plugin alone, may be empty for global plugins
                     fn get_trigger() -> _rt::String;
+                    /// Global plugins also get every query without a trigger, e.g. a calculator answering `2+2`.
+                    /// They should return nothing when the query isn't meant for them
+                    fn is_global() -> bool;
                     fn handle(input: _rt::String) -> _rt::Vec<ActionItem>;
+                    /// Used instead of handle for queries with the trigger, the query ends when it returns.
+                    /// Every pushed batch gives the plugin another timeout. Plugins with nothing to stream
+                    /// push what handle returns
+                    fn handle_streaming(input: _rt::String, sink: &ResultSink) -> ();
+                    /// The user picked an item that had an id, its action runs as well
+                    fn on_activate(item_id: _rt::String) -> ();
+                    /// Only asked for the selected item, and only if it has an id
+                    fn preview(item_id: _rt::String) -> PreviewContent;
+                    /// Last call before the instance goes away on reload or exit, for flushing state
+                    fn shutdown() -> ();
                 }
                 #[doc(hidden)]
-                macro_rules! __export_swift_launcher_runner_cabi {
+                macro_rules! __export_swift_launcher_runner_0_2_0_cabi {
                     ($ty:ident with_types_in $($path_to_types:tt)*) => {
                         const _ : () = { #[unsafe (export_name =
-                        "swift:launcher/runner#get-trigger")] unsafe extern "C" fn
+                        "swift:launcher/runner@0.2.0#init")] unsafe extern "C" fn
+                        export_init(arg0 : * mut u8, arg1 : usize,) -> * mut u8 { unsafe
+                        { $($path_to_types)*:: _export_init_cabi::<$ty > (arg0, arg1) } }
+                        #[unsafe (export_name =
+                        "cabi_post_swift:launcher/runner@0.2.0#init")] unsafe extern "C"
+                        fn _post_return_init(arg0 : * mut u8,) { unsafe {
+                        $($path_to_types)*:: __post_return_init::<$ty > (arg0) } }
+                        #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#get-trigger")] unsafe extern "C" fn
                         export_get_trigger() -> * mut u8 { unsafe { $($path_to_types)*::
                         _export_get_trigger_cabi::<$ty > () } } #[unsafe (export_name =
-                        "cabi_post_swift:launcher/runner#get-trigger")] unsafe extern "C"
-                        fn _post_return_get_trigger(arg0 : * mut u8,) { unsafe {
-                        $($path_to_types)*:: __post_return_get_trigger::<$ty > (arg0) } }
-                        #[unsafe (export_name = "swift:launcher/runner#handle")] unsafe
-                        extern "C" fn export_handle(arg0 : * mut u8, arg1 : usize,) -> *
-                        mut u8 { unsafe { $($path_to_types)*:: _export_handle_cabi::<$ty
-                        > (arg0, arg1) } } #[unsafe (export_name =
-                        "cabi_post_swift:launcher/runner#handle")] unsafe extern "C" fn
-                        _post_return_handle(arg0 : * mut u8,) { unsafe {
-                        $($path_to_types)*:: __post_return_handle::<$ty > (arg0) } } };
+                        "cabi_post_swift:launcher/runner@0.2.0#get-trigger")] unsafe
+                        extern "C" fn _post_return_get_trigger(arg0 : * mut u8,) { unsafe
+                        { $($path_to_types)*:: __post_return_get_trigger::<$ty > (arg0) }
+                        } #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#is-global")] unsafe extern "C" fn
+                        export_is_global() -> i32 { unsafe { $($path_to_types)*::
+                        _export_is_global_cabi::<$ty > () } } #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#handle")] unsafe extern "C" fn
+                        export_handle(arg0 : * mut u8, arg1 : usize,) -> * mut u8 {
+                        unsafe { $($path_to_types)*:: _export_handle_cabi::<$ty > (arg0,
+                        arg1) } } #[unsafe (export_name =
+                        "cabi_post_swift:launcher/runner@0.2.0#handle")] unsafe extern
+                        "C" fn _post_return_handle(arg0 : * mut u8,) { unsafe {
+                        $($path_to_types)*:: __post_return_handle::<$ty > (arg0) } }
+                        #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#handle-streaming")] unsafe extern
+                        "C" fn export_handle_streaming(arg0 : * mut u8, arg1 : usize,
+                        arg2 : i32,) { unsafe { $($path_to_types)*::
+                        _export_handle_streaming_cabi::<$ty > (arg0, arg1, arg2) } }
+                        #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#on-activate")] unsafe extern "C" fn
+                        export_on_activate(arg0 : * mut u8, arg1 : usize,) { unsafe {
+                        $($path_to_types)*:: _export_on_activate_cabi::<$ty > (arg0,
+                        arg1) } } #[unsafe (export_name =
+                        "swift:launcher/runner@0.2.0#preview")] unsafe extern "C" fn
+                        export_preview(arg0 : * mut u8, arg1 : usize,) -> * mut u8 {
+                        unsafe { $($path_to_types)*:: _export_preview_cabi::<$ty > (arg0,
+                        arg1) } } #[unsafe (export_name =
+                        "cabi_post_swift:launcher/runner@0.2.0#preview")] unsafe extern
+                        "C" fn _post_return_preview(arg0 : * mut u8,) { unsafe {
+                        $($path_to_types)*:: __post_return_preview::<$ty > (arg0) } }
+                        #[unsafe (export_name = "swift:launcher/runner@0.2.0#shutdown")]
+                        unsafe extern "C" fn export_shutdown() { unsafe {
+                        $($path_to_types)*:: _export_shutdown_cabi::<$ty > () } } };
                     };
                 }
                 #[doc(hidden)]
-                pub(crate) use __export_swift_launcher_runner_cabi;
+                pub(crate) use __export_swift_launcher_runner_0_2_0_cabi;
                 #[cfg_attr(target_pointer_width = "64", repr(align(8)))]
                 #[cfg_attr(target_pointer_width = "32", repr(align(4)))]
                 struct _RetArea(
                     [::core::mem::MaybeUninit<
                         u8,
-                    >; 2 * ::core::mem::size_of::<*const u8>()],
+                    >; 3 * ::core::mem::size_of::<*const u8>()],
                 );
                 static mut _RET_AREA: _RetArea = _RetArea(
-                    [::core::mem::MaybeUninit::uninit(); 2
+                    [::core::mem::MaybeUninit::uninit(); 3
                         * ::core::mem::size_of::<*const u8>()],
                 );
             }
@@ -206,6 +1240,124 @@ pub mod exports {
 mod _rt {
     #![allow(dead_code, clippy::all)]
     pub use alloc_crate::string::String;
+    pub use alloc_crate::vec::Vec;
+    pub unsafe fn string_lift(bytes: Vec<u8>) -> String {
+        if cfg!(debug_assertions) {
+            String::from_utf8(bytes).unwrap()
+        } else {
+            String::from_utf8_unchecked(bytes)
+        }
+    }
+    pub unsafe fn invalid_enum_discriminant<T>() -> T {
+        if cfg!(debug_assertions) {
+            panic!("invalid enum discriminant")
+        } else {
+            unsafe { core::hint::unreachable_unchecked() }
+        }
+    }
+    use core::fmt;
+    use core::marker;
+    use core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+    /// A type which represents a component model resource, either imported or
+    /// exported into this component.
+    ///
+    /// This is a low-level wrapper which handles the lifetime of the resource
+    /// (namely this has a destructor). The `T` provided defines the component model
+    /// intrinsics that this wrapper uses.
+    ///
+    /// One of the chief purposes of this type is to provide `Deref` implementations
+    /// to access the underlying data when it is owned.
+    ///
+    /// This type is primarily used in generated code for exported and imported
+    /// resources.
+    #[repr(transparent)]
+    pub struct Resource<T: WasmResource> {
+        handle: AtomicU32,
+        _marker: marker::PhantomData<T>,
+    }
+    /// A trait which all wasm resources implement, namely providing the ability to
+    /// drop a resource.
+    ///
+    /// This generally is implemented by generated code, not user-facing code.
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe trait WasmResource {
+        /// Invokes the `[resource-drop]...` intrinsic.
+        unsafe fn drop(handle: u32);
+    }
+    impl<T: WasmResource> Resource<T> {
+        #[doc(hidden)]
+        pub unsafe fn from_handle(handle: u32) -> Self {
+            debug_assert!(handle != u32::MAX);
+            Self {
+                handle: AtomicU32::new(handle),
+                _marker: marker::PhantomData,
+            }
+        }
+        /// Takes ownership of the handle owned by `resource`.
+        ///
+        /// Note that this ideally would be `into_handle` taking `Resource<T>` by
+        /// ownership. The code generator does not enable that in all situations,
+        /// unfortunately, so this is provided instead.
+        ///
+        /// Also note that `take_handle` is in theory only ever called on values
+        /// owned by a generated function. For example a generated function might
+        /// take `Resource<T>` as an argument but then call `take_handle` on a
+        /// reference to that argument. In that sense the dynamic nature of
+        /// `take_handle` should only be exposed internally to generated code, not
+        /// to user code.
+        #[doc(hidden)]
+        pub fn take_handle(resource: &Resource<T>) -> u32 {
+            resource.handle.swap(u32::MAX, Relaxed)
+        }
+        #[doc(hidden)]
+        pub fn handle(resource: &Resource<T>) -> u32 {
+            resource.handle.load(Relaxed)
+        }
+    }
+    impl<T: WasmResource> fmt::Debug for Resource<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Resource").field("handle", &self.handle).finish()
+        }
+    }
+    impl<T: WasmResource> Drop for Resource<T> {
+        fn drop(&mut self) {
+            unsafe {
+                match self.handle.load(Relaxed) {
+                    u32::MAX => {}
+                    other => T::drop(other),
+                }
+            }
+        }
+    }
+    pub use alloc_crate::alloc;
+    pub fn as_f32<T: AsF32>(t: T) -> f32 {
+        t.as_f32()
+    }
+    pub trait AsF32 {
+        fn as_f32(self) -> f32;
+    }
+    impl<'a, T: Copy + AsF32> AsF32 for &'a T {
+        fn as_f32(self) -> f32 {
+            (*self).as_f32()
+        }
+    }
+    impl AsF32 for f32 {
+        #[inline]
+        fn as_f32(self) -> f32 {
+            self as f32
+        }
+    }
+    pub unsafe fn bool_lift(val: u8) -> bool {
+        if cfg!(debug_assertions) {
+            match val {
+                0 => false,
+                1 => true,
+                _ => panic!("invalid bool discriminant"),
+            }
+        } else {
+            val != 0
+        }
+    }
     #[cfg(target_arch = "wasm32")]
     pub fn run_ctors_once() {
         wit_bindgen_rt::run_ctors_once();
@@ -217,15 +1369,6 @@ mod _rt {
         let layout = alloc::Layout::from_size_align_unchecked(size, align);
         alloc::dealloc(ptr, layout);
     }
-    pub use alloc_crate::vec::Vec;
-    pub unsafe fn string_lift(bytes: Vec<u8>) -> String {
-        if cfg!(debug_assertions) {
-            String::from_utf8(bytes).unwrap()
-        } else {
-            String::from_utf8_unchecked(bytes)
-        }
-    }
-    pub use alloc_crate::alloc;
     extern crate alloc as alloc_crate;
 }
 /// Generates `#[unsafe(no_mangle)]` functions to export the specified type as
@@ -252,25 +1395,39 @@ macro_rules! __export_plugin_world_impl {
     };
     ($ty:ident with_types_in $($path_to_types_root:tt)*) => {
         $($path_to_types_root)*::
-        exports::swift::launcher::runner::__export_swift_launcher_runner_cabi!($ty
+        exports::swift::launcher::runner::__export_swift_launcher_runner_0_2_0_cabi!($ty
         with_types_in $($path_to_types_root)*:: exports::swift::launcher::runner);
     };
 }
 #[doc(inline)]
 pub(crate) use __export_plugin_world_impl as export;
 #[cfg(target_arch = "wasm32")]
-#[unsafe(
-    link_section = "component-type:wit-bindgen:0.41.0:swift:launcher:plugin-world:encoded world"
-)]
+#[unsafe(link_section = "component-type:wit-bindgen:0.41.0:swift:launcher@0.2.0:plugin-world:encoded world")]
 #[doc(hidden)]
 #[allow(clippy::octal_escapes)]
-pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 287] = *b"\
-\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\x9c\x01\x01A\x02\x01\
-A\x02\x01B\x07\x01r\x03\x04names\x04execs\x08keywordss\x04\0\x0baction-item\x03\0\
-\0\x01@\0\0s\x04\0\x0bget-trigger\x01\x02\x01p\x01\x01@\x01\x05inputs\0\x03\x04\0\
-\x06handle\x01\x04\x04\0\x15swift:launcher/runner\x05\0\x04\0\x1bswift:launcher/\
-plugin-world\x04\0\x0b\x12\x01\0\x0cplugin-world\x03\0\0\0G\x09producers\x01\x0c\
-processed-by\x02\x0dwit-component\x070.227.1\x10wit-bindgen-rust\x060.41.0";
+pub static __WIT_BINDGEN_COMPONENT_TYPE: [u8; 938] = *b"\
+\0asm\x0d\0\x01\0\0\x19\x16wit-component-encoding\x04\0\x07\xa7\x06\x01A\x02\x01\
+A\x0a\x01B\x03\x01j\0\x01s\x01@\x01\x04texts\0\0\x04\0\x08set-text\x01\x01\x03\0\
+\x1eswift:launcher/clipboard@0.2.0\x05\0\x01B\x07\x01ps\x01q\x05\x05spawn\x01\0\0\
+\x08open-uri\x01s\0\x09copy-text\x01s\0\x05shell\x01s\0\x04none\0\0\x04\0\x06act\
+ion\x03\0\x01\x01ks\x01kv\x01r\x07\x04names\x06action\x02\x08keywordss\x0bdescri\
+ption\x03\x04icon\x03\x05score\x04\x02id\x03\x04\0\x0baction-item\x03\0\x05\x03\0\
+\x1aswift:launcher/types@0.2.0\x05\x01\x02\x03\0\x01\x0baction-item\x01B\x07\x02\
+\x03\x02\x01\x02\x04\0\x0baction-item\x03\0\0\x04\0\x0bresult-sink\x03\x01\x01h\x02\
+\x01p\x01\x01@\x02\x04self\x03\x05items\x04\0\x7f\x04\0\x18[method]result-sink.p\
+ush\x01\x05\x03\0\x1cswift:launcher/results@0.2.0\x05\x03\x02\x03\0\x02\x0bresul\
+t-sink\x01B\x1b\x02\x03\x02\x01\x02\x04\0\x0baction-item\x03\0\0\x02\x03\x02\x01\
+\x04\x04\0\x0bresult-sink\x03\0\x02\x01o\x02ss\x01p\x04\x01q\x04\x04text\x01s\0\x05\
+image\x01s\0\x07details\x01\x05\0\x04none\0\0\x04\0\x0fpreview-content\x03\0\x06\
+\x01j\0\x01s\x01@\x01\x06configs\0\x08\x04\0\x04init\x01\x09\x01@\0\0s\x04\0\x0b\
+get-trigger\x01\x0a\x01@\0\0\x7f\x04\0\x09is-global\x01\x0b\x01p\x01\x01@\x01\x05\
+inputs\0\x0c\x04\0\x06handle\x01\x0d\x01h\x03\x01@\x02\x05inputs\x04sink\x0e\x01\
+\0\x04\0\x10handle-streaming\x01\x0f\x01@\x01\x07item-ids\x01\0\x04\0\x0bon-acti\
+vate\x01\x10\x01@\x01\x07item-ids\0\x07\x04\0\x07preview\x01\x11\x01@\0\x01\0\x04\
+\0\x08shutdown\x01\x12\x04\0\x1bswift:launcher/runner@0.2.0\x05\x05\x04\0!swift:\
+launcher/plugin-world@0.2.0\x04\0\x0b\x12\x01\0\x0cplugin-world\x03\0\0\0G\x09pr\
+oducers\x01\x0cprocessed-by\x02\x0dwit-component\x070.227.1\x10wit-bindgen-rust\x06\
+0.41.0";
 #[inline(never)]
 #[doc(hidden)]
 pub fn __link_custom_section_describing_imports() {
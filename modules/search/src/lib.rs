@@ -1,64 +1,102 @@
-use bindings::exports::swift::launcher::runner::{ActionItem, Guest};
+use bindings::exports::swift::launcher::runner::{Guest, PreviewContent, ResultSink};
+use bindings::swift::launcher::types::{Action, ActionItem};
 mod bindings;
 
 use ini::Ini;
 use std::collections::HashMap;
-use std::env;
-use std::fs;
-use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// Parsed once in init, the instance stays alive between keystrokes
+static ENGINES: OnceLock<HashMap<String, String>> = OnceLock::new();
 
 struct SearchPlugin;
 
 impl Guest for SearchPlugin {
+    /// Engines come from `search.conf` and the plugin's section of `config.conf`, the latter wins.
+    fn init(config: String) -> Result<(), String> {
+        let mut engine_map: HashMap<String, String> = HashMap::new();
+        engine_map.insert("g".to_string(), "google.com/search".to_string());
+        engine_map.insert("d".to_string(), "duckduckgo.com/".to_string());
+
+        match Ini::load_from_file("/config/search.conf") {
+            Ok(conf) => add_engines(&mut engine_map, &conf),
+            Err(_) => println!("No search.conf"),
+        }
+        let section = Ini::load_from_str(&config).map_err(|e| e.to_string())?;
+        add_engines(&mut engine_map, &section);
+
+        let _ = ENGINES.set(engine_map);
+        Ok(())
+    }
+
     fn get_trigger() -> String {
         "@".to_string()
     }
 
-    // TODO: NEED TO ADD PARSING FROM CONFIG FILE
+    fn is_global() -> bool {
+        false
+    }
+
     fn handle(input: String) -> Vec<ActionItem> {
-        println!("internet_mode");
         let cleaned = input.trim_start_matches('@').trim();
         let mut action = Vec::new();
-        let mut engine_map: HashMap<String, String> = HashMap::new();
-        engine_map.insert("g".to_string(), "google.com/search".to_string());
-        engine_map.insert("d".to_string(), "duckduckgo.com/".to_string());
-
-        let mut config_path = PathBuf::from("/config/search.conf");
-
-        if let Ok(conf) = Ini::load_from_file(&config_path) {
-            if let Some(section) = conf.section(None::<String>) {
-                for (key, value) in section.iter() {
-                    engine_map.insert(key.to_string(), value.to_string());
-                }
-            }
-        } else {
-            println!("Failed to get config");
-        }
+        let Some(engine_map) = ENGINES.get() else {
+            return action;
+        };
 
         if let Some((engine, query)) = cleaned.split_once(' ') {
             if let Some(engine_url) = engine_map.get(engine) {
-                let exec = query.replace(" ", "+");
+                let exec = urlencoding::encode(query);
                 println!("domain: {:?} search: {:?}", engine, exec);
 
-                let search_url = format!("xdg-open https://{}?q={}", engine_url, exec);
+                let search_url = format!("https://{}?q={}", engine_url, exec);
                 action.push(ActionItem {
                     name: format!("hey"),
-                    exec: search_url.into(),
+                    action: Action::OpenUri(search_url),
                     keywords: "".into(),
+                    description: None,
+                    icon: Some("web-browser".into()),
+                    score: None,
+                    id: None,
                 });
             } else {
                 action.push(ActionItem {
                     name: format!("Using {}", engine),
-                    exec: "".into(),
+                    action: Action::None,
                     keywords: "".into(),
+                    description: None,
+                    icon: None,
+                    score: None,
+                    id: None,
                 });
             }
         } else {
-            action.extend(show_search_engines(&engine_map));
+            action.extend(show_search_engines(engine_map));
         }
 
         action
     }
+
+    // The rows are built without asking the engine anything
+    fn handle_streaming(input: String, sink: &ResultSink) {
+        sink.push(&Self::handle(input));
+    }
+
+    fn on_activate(_item_id: String) {}
+
+    fn preview(_item_id: String) -> PreviewContent {
+        PreviewContent::None
+    }
+
+    fn shutdown() {}
+}
+
+fn add_engines(engine_map: &mut HashMap<String, String>, conf: &Ini) {
+    if let Some(section) = conf.section(None::<String>) {
+        for (key, value) in section.iter() {
+            engine_map.insert(key.to_string(), value.to_string());
+        }
+    }
 }
 
 fn show_search_engines(map: &HashMap<String, String>) -> Vec<ActionItem> {
@@ -68,8 +106,12 @@ fn show_search_engines(map: &HashMap<String, String>) -> Vec<ActionItem> {
 
         engines.push(ActionItem {
             name: value.to_string(),
-            exec: key.to_string(),
+            action: Action::None,
             keywords: "".into(),
+            description: Some(format!("@{} <query>", key)),
+            icon: None,
+            score: None,
+            id: None,
         })
     }
     engines
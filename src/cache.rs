@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use wasmtime::Engine;
+use wasmtime::component::Component;
+
+const EXTENSION: &str = "cwasm";
+
+/// Name of the cache entry for `wasm` compiled by `engine`, a SHA-256 over both. The engine's
+/// compatibility hash covers the wasmtime version and every config setting that changes the
+/// machine code, so a changed plugin or a launcher update simply misses the cache.
+pub fn key(engine: &Engine, wasm: &[u8]) -> String {
+    let mut hasher = Sha256Hasher(Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hasher.0.update(wasm);
+    hasher
+        .0
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Feeds what a `Hash` impl writes into SHA-256, the compatibility hash is only exposed that way.
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unreachable!("only used to feed the digest")
+    }
+}
+
+/// Deserializes the cached component for `key`, or compiles `wasm` and caches it.
+pub fn load(engine: &Engine, key: &str, wasm: &[u8]) -> wasmtime::Result<Component> {
+    let path = entry_path(key);
+
+    if is_trusted(&path) {
+        // SAFETY: only files the launcher wrote itself get here, see `is_trusted`
+        match unsafe { Component::deserialize_file(engine, &path) } {
+            Ok(component) => return Ok(component),
+            Err(e) => eprintln!("Discarding cached {}: {}", path.display(), e),
+        }
+    } else if path.exists() {
+        eprintln!(
+            "Refusing to load {}, it wasn't written by the launcher",
+            path.display()
+        );
+    }
+
+    let cwasm = engine.precompile_component(wasm)?;
+    if let Err(e) = store(&path, &cwasm) {
+        eprintln!("Failed to cache {}: {}", path.display(), e);
+    }
+    // SAFETY: just produced by this engine
+    unsafe { Component::deserialize(engine, &cwasm) }
+}
+
+/// Whether a usable entry for `key` is there, so loading it skips compiling.
+pub fn is_cached(key: &str) -> bool {
+    is_trusted(&entry_path(key))
+}
+
+/// Deletes every entry except `keys`, e.g. the ones of plugins that were since changed or removed,
+/// and what `store` left behind when a launcher died while writing.
+pub fn retain(keys: &HashSet<String>) {
+    let Ok(entries) = fs::read_dir(cache_dir()) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let stale = match name.split('.').collect::<Vec<_>>()[..] {
+            [key, EXTENSION] => !keys.contains(key),
+            // `<key>.cwasm.<pid>`, unless that launcher is still writing it
+            [_, EXTENSION, pid] => {
+                pid.parse::<u32>().is_ok() && !Path::new("/proc").join(pid).exists()
+            }
+            _ => false,
+        };
+        if stale {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+fn entry_path(key: &str) -> PathBuf {
+    cache_dir().join(format!("{}.{}", key, EXTENSION))
+}
+
+/// `$XDG_CACHE_HOME/swift`.
+fn cache_dir() -> PathBuf {
+    let cache_home = std::env::var("XDG_CACHE_HOME")
+        .unwrap_or_else(|_| format!("{}/.cache", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(format!("{}/swift", cache_home))
+}
+
+/// Whether the entry was written by `store`: a regular file owned by us that nobody else
+/// can write, in a directory nobody else can write either. Deserializing runs the native
+/// code inside, so anything else is never loaded.
+fn is_trusted(path: &Path) -> bool {
+    let Some(uid) = current_uid() else {
+        return false;
+    };
+    let private = |path: &Path, is_dir: bool| {
+        fs::symlink_metadata(path).is_ok_and(|meta| {
+            meta.uid() == uid
+                && meta.mode() & 0o022 == 0
+                && if is_dir {
+                    meta.is_dir()
+                } else {
+                    meta.is_file()
+                }
+        })
+    };
+
+    path.parent().is_some_and(|dir| private(dir, true)) && private(path, false)
+}
+
+/// Writes the entry readable and writable by us alone. It's renamed into place at the end,
+/// so a crash never leaves half an entry where `load` looks, only a partial file for `retain`.
+fn store(path: &Path, cwasm: &[u8]) -> std::io::Result<()> {
+    let dir = cache_dir();
+    DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+
+    let partial = path.with_extension(format!("{}.{}", EXTENSION, std::process::id()));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&partial)?;
+    let written = file.write_all(cwasm).and_then(|_| file.sync_all());
+    if let Err(e) = written.and_then(|_| fs::rename(&partial, path)) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// The effective uid, as the owner of our own `/proc` entry.
+fn current_uid() -> Option<u32> {
+    fs::metadata("/proc/self").ok().map(|meta| meta.uid())
+}
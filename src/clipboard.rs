@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use wl_clipboard_rs::copy::{MimeType, Options, Source};
+
+/// Argument that turns the launcher binary into a clipboard owner
+pub const SERVE_ARG: &str = "--serve-clipboard";
+
+/// Puts `text` on the Wayland clipboard through the data-control protocol.
+///
+/// The selection is served by a separate copy of the launcher, so it survives
+/// the launcher window closing or the whole process exiting.
+pub fn copy_text(text: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(std::env::current_exe()?)
+        .arg(SERVE_ARG)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    child
+        .stdin
+        .take()
+        .ok_or("Clipboard process has no stdin")?
+        .write_all(text.as_bytes())?;
+
+    // Reap it once someone else takes over the selection
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
+
+/// Entry point of the serving process, blocks until the selection is replaced.
+pub fn serve_stdin() -> Result<(), Box<dyn Error>> {
+    let mut opts = Options::new();
+    opts.foreground(true);
+    opts.copy(Source::StdIn, MimeType::Text)?;
+    Ok(())
+}
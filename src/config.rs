@@ -2,9 +2,10 @@ use ini::Ini;
 use std::collections::HashMap;
 use std::error::Error;
 use std::path::PathBuf;
+use std::time::Duration;
 // use std::path::{self, Path};
 
-use crate::ActionItem;
+use crate::{ActionItem, ActionKind};
 
 pub fn load_config() -> Vec<ActionItem> {
     let mut actions = Vec::new();
@@ -23,25 +24,202 @@ pub fn load_config() -> Vec<ActionItem> {
             let mut name = prop.get("name").unwrap_or("").to_string();
             let mut exec = prop.get("exec").unwrap_or("").to_string();
             let mut keywords = prop.get("keywords").unwrap_or("").to_string();
+            let mut cwd = prop.get("cwd").unwrap_or("").to_string();
 
             // Replace custom variables ($editor, etc.)
             for (k, v) in &vars {
                 name = name.replace(k, v);
                 exec = exec.replace(k, v);
                 keywords = keywords.replace(k, v);
+                cwd = cwd.replace(k, v);
             }
 
             actions.push(ActionItem {
                 name: name.into(),
                 exec: exec.into(),
                 keywords: keywords.into(),
-                icon: Default::default(),
+                path: cwd.into(),
+                terminal: prop.get("terminal") == Some("true"),
+                kind: if prop.get("shell") == Some("true") {
+                    ActionKind::Shell
+                } else {
+                    ActionKind::Exec
+                },
+                ..Default::default()
             });
         }
     }
     actions
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchBackend {
+    /// Apps stay children of the launcher
+    #[default]
+    Direct,
+    /// Every app gets its own transient systemd user scope
+    Systemd,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Settings {
+    pub launch_backend: LaunchBackend,
+    /// Terminal emulator for `Terminal=true` apps, detected when unset
+    pub terminal: Option<String>,
+}
+
+pub fn load_settings() -> Settings {
+    let mut settings = Settings::default();
+    let conf = Ini::load_from_file(get_config_file().unwrap()).unwrap_or_default();
+
+    if let Some(section) = conf.section(Some("settings")) {
+        match section.get("launch-backend") {
+            Some("systemd") => settings.launch_backend = LaunchBackend::Systemd,
+            Some("direct") | None => {}
+            Some(other) => eprintln!("Unknown launch-backend {}, using direct", other),
+        }
+        settings.terminal = section.get("terminal").map(str::to_string);
+    }
+
+    settings
+}
+
+// Keys of a `[plugin:<id>]` section read by the launcher, the rest is the plugin's own config
+const PLUGIN_KEYS: &[&str] = &[
+    "trigger",
+    "timeout",
+    "memory",
+    "table-elements",
+    "max-results",
+    "storage",
+    "enabled",
+];
+
+/// Overrides for a single plugin, set in a `[plugin:<id>]` section.
+#[derive(Debug, Clone)]
+pub struct PluginSettings {
+    /// Replaces the trigger the plugin asks for, e.g. to settle a conflict
+    pub trigger: Option<String>,
+    /// How long one call into the plugin may run before it's stopped
+    pub timeout: Duration,
+    /// Linear memory ceiling in bytes
+    pub memory: usize,
+    /// Table elements ceiling
+    pub table_elements: usize,
+    /// How many results a global plugin may add to a normal search
+    pub max_results: usize,
+    /// Bytes the plugin may keep in its data directory
+    pub storage: u64,
+    /// Turned off plugins are neither compiled nor run
+    pub enabled: bool,
+    /// Every other key of the section as ini lines, handed to the plugin's `init`
+    pub config: String,
+}
+
+impl Default for PluginSettings {
+    fn default() -> Self {
+        Self {
+            trigger: None,
+            timeout: Duration::from_millis(1000),
+            memory: 64 << 20,
+            table_elements: 10_000,
+            max_results: 3,
+            storage: 16 << 20,
+            enabled: true,
+            config: String::new(),
+        }
+    }
+}
+
+/// Settings for every plugin with a `[plugin:<id>]` section, keyed by id.
+pub fn load_plugin_settings() -> HashMap<String, PluginSettings> {
+    let mut plugins = HashMap::new();
+    let conf = Ini::load_from_file(get_config_file().unwrap()).unwrap_or_default();
+
+    for (sec, prop) in &conf {
+        let Some(id) = sec.and_then(|s| s.strip_prefix("plugin:")) else {
+            continue;
+        };
+
+        let mut settings = PluginSettings {
+            trigger: prop.get("trigger").map(str::to_string),
+            ..Default::default()
+        };
+        if let Some(ms) = prop.get("timeout").and_then(|v| v.parse().ok()) {
+            settings.timeout = Duration::from_millis(ms);
+        }
+        if let Some(mb) = prop.get("memory").and_then(|v| v.parse::<usize>().ok()) {
+            settings.memory = mb << 20;
+        }
+        if let Some(elements) = prop.get("table-elements").and_then(|v| v.parse().ok()) {
+            settings.table_elements = elements;
+        }
+        if let Some(max) = prop.get("max-results").and_then(|v| v.parse().ok()) {
+            settings.max_results = max;
+        }
+        if let Some(mb) = prop.get("storage").and_then(|v| v.parse::<u64>().ok()) {
+            settings.storage = mb << 20;
+        }
+        if let Some(enabled) = prop.get("enabled") {
+            settings.enabled = enabled.trim() != "false";
+        }
+        for (key, value) in prop.iter().filter(|(key, _)| !PLUGIN_KEYS.contains(key)) {
+            settings.config.push_str(&format!("{} = {}\n", key, value));
+        }
+        plugins.insert(id.to_string(), settings);
+    }
+
+    plugins
+}
+
+/// Sets `key` in the `[plugin:<id>]` section, adding either if needed. Edited line by line,
+/// so the rest of the file and its comments stay as the user wrote them.
+pub fn set_plugin_value(id: &str, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    let path = get_config_file()?;
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let entry = format!("{} = {}", key, value);
+
+    let header = format!("[plugin:{}]", id);
+    match lines.iter().position(|line| line.trim() == header) {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with('['))
+                .map_or(lines.len(), |offset| start + 1 + offset);
+            let existing = (start + 1..end).find(|&i| {
+                lines[i]
+                    .split_once('=')
+                    .is_some_and(|(k, _)| k.trim() == key)
+            });
+            match existing {
+                Some(i) => lines[i] = entry,
+                None => {
+                    // Right after the last setting, not after the blank lines before the next section
+                    let last = (start..end)
+                        .rev()
+                        .find(|&i| !lines[i].trim().is_empty())
+                        .unwrap_or(start);
+                    lines.insert(last + 1, entry);
+                }
+            }
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(entry);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
 fn get_config_file() -> Result<PathBuf, Box<dyn Error>> {
     let config_dir = std::env::var("HOME")?;
     let file = PathBuf::from(format!("{}/.config/swift/config.conf", config_dir));
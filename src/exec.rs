@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::clipboard;
+use crate::config::{LaunchBackend, Settings};
+use crate::scope::ScopeLauncher;
+use crate::{ActionItem, ActionKind};
+
+// Tried in order when neither the config nor $TERMINAL name one
+const FALLBACK_TERMINALS: &[&str] = &[
+    "foot",
+    "kitty",
+    "alacritty",
+    "ghostty",
+    "wezterm",
+    "konsole",
+    "gnome-terminal",
+    "ptyxis",
+    "xfce4-terminal",
+    "xterm",
+];
+
+pub struct Launcher {
+    scope: Option<ScopeLauncher>,
+    // Command prefix that runs the rest of the argv inside a terminal emulator
+    terminal: Option<Vec<String>>,
+}
+
+impl Launcher {
+    pub fn new(settings: &Settings) -> Self {
+        let scope = match settings.launch_backend {
+            LaunchBackend::Direct => None,
+            LaunchBackend::Systemd => match ScopeLauncher::session() {
+                Ok(scope) => Some(scope),
+                Err(e) => {
+                    eprintln!("Failed to reach systemd user manager, launching directly: {}", e);
+                    None
+                }
+            },
+        };
+
+        let terminal = find_terminal(settings.terminal.as_deref());
+        println!("Terminal: {:?}", terminal);
+
+        Self { scope, terminal }
+    }
+
+    pub fn launch(&self, action: &ActionItem) -> Result<(), Box<dyn Error>> {
+        match action.kind {
+            // Handled by the plugin manager
+            ActionKind::None | ActionKind::AllowPlugin => return Ok(()),
+            ActionKind::CopyText => return clipboard::copy_text(&action.exec),
+            ActionKind::OpenUri => return open_uri(&action.exec),
+            ActionKind::Exec | ActionKind::Shell => {}
+        }
+
+        let mut argv = get_argv(action)?;
+        if action.terminal {
+            let terminal = self
+                .terminal
+                .as_ref()
+                .ok_or("No terminal emulator found, set one with terminal= in [settings]")?;
+            argv.splice(0..0, terminal.iter().cloned());
+        }
+
+        let pid = spawn(&argv, &action.path)?;
+
+        if let Some(scope) = &self.scope {
+            let app_id = if action.desktop_id.is_empty() {
+                action.name.as_str()
+            } else {
+                action.desktop_id.as_str()
+            };
+
+            // The app is already running, so a failure here only costs the accounting
+            match scope.move_to_scope(app_id, pid) {
+                Ok(unit) => println!("Moved {} into {}", pid, unit),
+                Err(e) => eprintln!("Failed to create scope for {}: {}", app_id, e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Spawns the argv directly, optionally in `cwd`, and returns its pid.
+pub fn spawn(argv: &[String], cwd: &str) -> Result<u32, Box<dyn Error>> {
+    let (program, args) = argv.split_first().ok_or("Empty command")?;
+
+    let mut command = Command::new(program);
+    command.args(args);
+    if !cwd.is_empty() {
+        command.current_dir(cwd);
+    }
+
+    let mut child = command.spawn()?;
+    let pid = child.id();
+
+    // Reap the child once it exits so a long running launcher doesn't collect zombies
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(pid)
+}
+
+/// Shell or Exec line of the action as an argv, without any terminal wrapping.
+pub fn get_argv(action: &ActionItem) -> Result<Vec<String>, Box<dyn Error>> {
+    match action.kind {
+        ActionKind::Shell => Ok(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            action.exec.to_string(),
+        ]),
+        ActionKind::Exec => parse_exec(
+            &action.exec,
+            &FieldCodes {
+                name: &action.name,
+                icon: &action.icon_name,
+                file: &action.file,
+            },
+        ),
+        _ => Err(format!("{} is not a command", action.name).into()),
+    }
+}
+
+/// Asks the desktop portal to open `uri`, falling back to `xdg-open` without one.
+fn open_uri(uri: &str) -> Result<(), Box<dyn Error>> {
+    let via_portal = Connection::session().and_then(|connection| {
+        let options: HashMap<&str, Value> = HashMap::new();
+        connection.call_method(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            Some("org.freedesktop.portal.OpenURI"),
+            "OpenURI",
+            &("", uri, options),
+        )
+    });
+
+    if let Err(e) = via_portal {
+        println!("Portal OpenURI failed, using xdg-open: {}", e);
+        spawn(&["xdg-open".to_string(), uri.to_string()], "")?;
+    }
+
+    Ok(())
+}
+
+/// A configured or detected terminal as a prefix for the command to run in it.
+/// A bare terminal name gets the flag it needs to run a command, anything longer is used as is.
+fn find_terminal(configured: Option<&str>) -> Option<Vec<String>> {
+    let no_codes = FieldCodes {
+        name: "",
+        icon: "",
+        file: "",
+    };
+
+    let candidate = configured
+        .map(str::to_string)
+        .or_else(|| env::var("TERMINAL").ok())
+        .filter(|term| !term.trim().is_empty());
+
+    if let Some(term) = candidate {
+        let mut argv = parse_exec(&term, &no_codes).ok()?;
+        if argv.len() == 1
+            && let Some(flag) = exec_flag(&argv[0])
+        {
+            argv.extend(flag.split(' ').map(str::to_string));
+        }
+        return Some(argv);
+    }
+
+    // Picks the user's preferred terminal on its own and takes the command as is
+    if is_executable("xdg-terminal-exec") {
+        return Some(vec!["xdg-terminal-exec".to_string()]);
+    }
+
+    FALLBACK_TERMINALS
+        .iter()
+        .find(|term| is_executable(term))
+        .map(|term| {
+            let mut argv = vec![term.to_string()];
+            if let Some(flag) = exec_flag(term) {
+                argv.extend(flag.split(' ').map(str::to_string));
+            }
+            argv
+        })
+}
+
+/// Flag each terminal expects before the command it should run.
+fn exec_flag(terminal: &str) -> Option<&'static str> {
+    let name = Path::new(terminal).file_name()?.to_str()?;
+    match name {
+        "kitty" | "foot" | "tilix" | "xdg-terminal-exec" => None,
+        "gnome-terminal" | "ptyxis" | "kgx" | "blackbox" => Some("--"),
+        "xfce4-terminal" | "terminator" | "guake" => Some("-x"),
+        "wezterm" => Some("start --"),
+        _ => Some("-e"),
+    }
+}
+
+pub fn is_executable(program: &str) -> bool {
+    let is_exec_file = |path: &Path| {
+        fs::metadata(path).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    };
+
+    if program.contains('/') {
+        return is_exec_file(Path::new(program));
+    }
+
+    env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .any(|dir| is_exec_file(&Path::new(dir).join(program)))
+}
+
+/// Values substituted for `%c`, `%i` and `%k`.
+pub struct FieldCodes<'a> {
+    pub name: &'a str,
+    pub icon: &'a str,
+    pub file: &'a str,
+}
+
+/// Splits an `Exec` value into an argv following the Desktop Entry quoting rules and
+/// expands its field codes. No files or URLs are ever passed, so `%f`, `%F`, `%u` and `%U` vanish.
+pub fn parse_exec(exec: &str, codes: &FieldCodes) -> Result<Vec<String>, Box<dyn Error>> {
+    let exec = unescape(exec);
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Tracks whether `current` is an argument even if it's empty, e.g. `""`
+    let mut started = false;
+    let mut quoted = false;
+    let mut chars = exec.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' => quoted = false,
+                '\\' => match chars.peek() {
+                    Some(&next @ ('"' | '`' | '$' | '\\')) => {
+                        current.push(next);
+                        chars.next();
+                    }
+                    _ => current.push('\\'),
+                },
+                _ => current.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                quoted = true;
+                started = true;
+            }
+            ' ' | '\t' | '\n' => {
+                if started {
+                    args.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            '%' => match chars.next() {
+                Some('%') => {
+                    current.push('%');
+                    started = true;
+                }
+                Some('c') if !codes.name.is_empty() => {
+                    current.push_str(codes.name);
+                    started = true;
+                }
+                Some('k') if !codes.file.is_empty() => {
+                    current.push_str(codes.file);
+                    started = true;
+                }
+                // Expands to two arguments, so it's only valid on its own
+                Some('i') if !codes.icon.is_empty() && !started => {
+                    args.push("--icon".to_string());
+                    current.push_str(codes.icon);
+                    started = true;
+                }
+                Some('f' | 'F' | 'u' | 'U' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm' | 'c' | 'k' | 'i') => {}
+                Some(other) => return Err(format!("Invalid field code %{}", other).into()),
+                None => return Err("Exec ends with a lone %".into()),
+            },
+            _ => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if quoted {
+        return Err("Unterminated quote in Exec".into());
+    }
+    if started {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+/// Quotes an argv into an `Exec` style line that `parse_exec` splits back into the same arguments.
+pub fn quote_argv(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_./:=,+@".contains(c));
+            if plain {
+                return arg.clone();
+            }
+
+            // Escaped once for the quoting rules, then every backslash again for the string type
+            let mut quoted = String::from("\"");
+            for c in arg.chars() {
+                match c {
+                    '"' | '`' | '$' => {
+                        quoted.push_str("\\\\");
+                        quoted.push(c);
+                    }
+                    '\\' => quoted.push_str("\\\\\\\\"),
+                    _ => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Undoes the escapes every Desktop Entry string value may contain (`\s`, `\n`, `\t`, `\r`, `\\`).
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODES: FieldCodes = FieldCodes {
+        name: "Files",
+        icon: "org.gnome.Nautilus",
+        file: "/usr/share/applications/org.gnome.Nautilus.desktop",
+    };
+    const NO_CODES: FieldCodes = FieldCodes {
+        name: "",
+        icon: "",
+        file: "",
+    };
+
+    fn parse(exec: &str) -> Vec<String> {
+        parse_exec(exec, &CODES).unwrap()
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(parse(r#"sh -c "echo hi""#), ["sh", "-c", "echo hi"]);
+        assert_eq!(parse(r#"app "" x"#), ["app", "", "x"]);
+        // `\\"` in the file is `\"` after the string escapes, a literal quote inside quotes
+        assert_eq!(
+            parse(r#"sh -c "echo \\"a b\\" \\$HOME \\\\""#),
+            ["sh", "-c", r#"echo "a b" $HOME \"#]
+        );
+        assert!(parse_exec(r#"app "open"#, &CODES).is_err());
+    }
+
+    #[test]
+    fn expands_field_codes() {
+        assert_eq!(parse("app 100%%"), ["app", "100%"]);
+        assert_eq!(parse("app %f %U"), ["app"]);
+        assert_eq!(parse("app --title=%c"), ["app", "--title=Files"]);
+        assert_eq!(
+            parse("app %k"),
+            ["app", "/usr/share/applications/org.gnome.Nautilus.desktop"]
+        );
+        // Unset codes vanish like the file codes
+        assert_eq!(parse_exec("app %c %k %i", &NO_CODES).unwrap(), ["app"]);
+    }
+
+    #[test]
+    fn icon_only_expands_on_its_own() {
+        assert_eq!(parse("app %i"), ["app", "--icon", "org.gnome.Nautilus"]);
+        assert_eq!(parse("app x%i"), ["app", "x"]);
+    }
+
+    #[test]
+    fn rejects_invalid_field_codes() {
+        assert!(parse_exec("app %z", &CODES).is_err());
+        assert!(parse_exec("app %", &CODES).is_err());
+    }
+
+    #[test]
+    fn quoted_argv_parses_back() {
+        let argv: Vec<String> = [
+            "xdg-open",
+            "/home/me/My Files/a\"b",
+            "",
+            "$HOME",
+            "`id`",
+            r"back\slash",
+            "100%",
+            "tab\there",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+
+        assert_eq!(parse_exec(&quote_argv(&argv), &NO_CODES).unwrap(), argv);
+    }
+}
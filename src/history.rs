@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ActionItem;
+
+// A launch loses half its weight every week
+const HALF_LIFE_SECS: f64 = 7.0 * 24.0 * 60.0 * 60.0;
+// How much a well used action can climb over a better fuzzy match
+const FRECENCY_WEIGHT: f64 = 30.0;
+// Entries decayed below this are dropped on save
+const MIN_FRECENCY: f64 = 0.05;
+
+struct Entry {
+    count: u32,
+    last_used: u64,
+}
+
+pub struct History {
+    path: PathBuf,
+    entries: HashMap<String, Entry>,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let path = get_history_file();
+        let mut entries = HashMap::new();
+
+        if let Ok(contents) = fs::read_to_string(&path) {
+            for line in contents.lines() {
+                let mut fields = line.splitn(3, '\t');
+                if let (Some(count), Some(last_used), Some(key)) =
+                    (fields.next(), fields.next(), fields.next())
+                    && let (Ok(count), Ok(last_used)) = (count.parse(), last_used.parse())
+                {
+                    entries.insert(key.to_string(), Entry { count, last_used });
+                }
+            }
+        }
+
+        Self { path, entries }
+    }
+
+    pub fn record(&mut self, item: &ActionItem) {
+        let entry = self.entries.entry(key(item)).or_insert(Entry {
+            count: 0,
+            last_used: 0,
+        });
+        entry.count += 1;
+        entry.last_used = now();
+
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save history: {}", e);
+        }
+    }
+
+    /// Launch count decayed by the time since the last launch.
+    pub fn frecency(&self, item: &ActionItem) -> f64 {
+        self.entries
+            .get(&key(item))
+            .map(|entry| decayed(entry, now()))
+            .unwrap_or(0.0)
+    }
+
+    /// Fuzzy score with a logarithmic frecency bonus, so habits matter without drowning the query.
+    pub fn blend(&self, fuzzy_score: i64, item: &ActionItem) -> i64 {
+        fuzzy_score + (FRECENCY_WEIGHT * self.frecency(item).ln_1p()) as i64
+    }
+
+    /// Most used actions first, everything never launched keeps its original order.
+    pub fn sorted(&self, items: &[ActionItem]) -> Vec<ActionItem> {
+        let mut scored: Vec<(f64, ActionItem)> = items
+            .iter()
+            .map(|item| (self.frecency(item), item.clone()))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let now = now();
+        let mut contents = String::new();
+        for (key, entry) in &self.entries {
+            if decayed(entry, now) >= MIN_FRECENCY {
+                contents.push_str(&format!("{}\t{}\t{}\n", entry.count, entry.last_used, key));
+            }
+        }
+
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
+
+fn key(item: &ActionItem) -> String {
+    let key = if item.exec.is_empty() {
+        item.name.as_str()
+    } else {
+        item.exec.as_str()
+    };
+    key.replace(['\t', '\n'], " ")
+}
+
+fn decayed(entry: &Entry, now: u64) -> f64 {
+    let age = now.saturating_sub(entry.last_used) as f64;
+    entry.count as f64 * 0.5_f64.powf(age / HALF_LIFE_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn get_history_file() -> PathBuf {
+    let state_dir = std::env::var("XDG_STATE_HOME").unwrap_or_else(|_| {
+        format!(
+            "{}/.local/state",
+            std::env::var("HOME").unwrap_or_default()
+        )
+    });
+    PathBuf::from(format!("{}/swift/history", state_dir))
+}
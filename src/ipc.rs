@@ -0,0 +1,105 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Show,
+    Hide,
+    Toggle,
+    Reload,
+    Quit,
+}
+
+impl Request {
+    pub fn parse(cmd: &str) -> Option<Self> {
+        match cmd.trim() {
+            "show" => Some(Request::Show),
+            "hide" => Some(Request::Hide),
+            "toggle" => Some(Request::Toggle),
+            "reload" => Some(Request::Reload),
+            "quit" => Some(Request::Quit),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Request::Show => "show",
+            Request::Hide => "hide",
+            Request::Toggle => "toggle",
+            Request::Reload => "reload",
+            Request::Quit => "quit",
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) => PathBuf::from(format!("{}/swift-launcher.sock", dir)),
+        Err(_) => PathBuf::from(format!(
+            "/tmp/swift-launcher-{}.sock",
+            std::env::var("USER").unwrap_or_default()
+        )),
+    }
+}
+
+/// Sends a single request to a running daemon and waits for its reply.
+pub fn send(request: Request) -> Result<(), Box<dyn Error>> {
+    let mut stream = UnixStream::connect(socket_path())
+        .map_err(|e| format!("Could not reach swift-launcher daemon: {}", e))?;
+    writeln!(stream, "{}", request.as_str())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        err => Err(err.to_string().into()),
+    }
+}
+
+/// Binds the daemon socket and hands every request to `handler` from a background thread.
+pub fn listen<F>(handler: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(Request) + Send + 'static,
+{
+    let path = socket_path();
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!("A daemon is already listening on {:?}", path).into());
+    }
+    // Left behind by a daemon that didn't shut down cleanly
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)?;
+    println!("Listening on {:?}", path);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+
+            let mut writer = &stream;
+            match Request::parse(&line) {
+                Some(request) => {
+                    println!("IPC request: {:?}", request);
+                    let _ = writeln!(writer, "ok");
+                    handler(request);
+                }
+                None => {
+                    let _ = writeln!(writer, "unknown command: {}", line.trim());
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+pub fn cleanup() {
+    let _ = std::fs::remove_file(socket_path());
+}
@@ -3,10 +3,11 @@ spell_framework::generate_widgets![LauncherWindow];
 
 use slint::{Model, ModelRc, VecModel};
 use std::error::Error;
-use std::process::Command;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::{Arc, Mutex};
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
@@ -18,12 +19,37 @@ use spell_framework::{
 
 use icon_finder::find_icon;
 
+mod cache;
+mod clipboard;
 mod config;
+mod exec;
+mod history;
+mod ipc;
+mod permissions;
+mod plugin_cli;
 mod plugins;
+mod scope;
 mod scraper;
+mod storage;
 mod theme;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let daemon = match std::env::args().nth(1).as_deref() {
+        None => false,
+        Some("--daemon") => true,
+        Some(clipboard::SERVE_ARG) => return clipboard::serve_stdin(),
+        Some("plugins") => return plugin_cli::run(&std::env::args().skip(2).collect::<Vec<_>>()),
+        Some(cmd) => {
+            let request = ipc::Request::parse(cmd).ok_or_else(|| {
+                format!(
+                    "Unknown command: {}\nUsage: Swift-launcher [--daemon | show | hide | toggle | reload | quit | plugins]",
+                    cmd
+                )
+            })?;
+            return ipc::send(request);
+        }
+    };
+
     println!("Hello, world!");
 
     let window_size = theme::get_window_info();
@@ -42,145 +68,481 @@ fn main() -> Result<(), Box<dyn Error>> {
     // let ui = LauncherWindow::new()?;
     let _theme = theme::apply_theme(&ui);
 
-    let manager = Arc::new(Mutex::new(plugins::PluginManager::new()));
-    let manager_bg = Arc::clone(&manager);
-
-    rayon::spawn(move || {
-        let mut mg = manager_bg.lock().unwrap();
-        if let Err(e) = mg.load_all() {
-            eprintln!("Failed to load plugins: {}", e);
-        }
-    });
+    let plugins = plugins::Plugins::new();
+    load_plugins(&plugins, ui.as_weak());
     // manager.load_all()?;
+    let ui_weak = ui.as_weak();
+    let watched = plugins.watch(move || {
+        // Also brings up compile errors, they're listed with the other notices
+        let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_refresh());
+    });
+    if let Err(e) = watched {
+        eprintln!("Failed to watch the plugin directory, changes need a reload: {}", e);
+    }
 
-    let config = config::load_config();
-    let mut all_actions = scraper::get_programs();
-    all_actions.extend(config);
+    let all_actions = load_actions();
     // println!("{:?}", all_actions);
+    let history = Rc::new(RefCell::new(history::History::load()));
+    let launcher = Rc::new(RefCell::new(exec::Launcher::new(&config::load_settings())));
 
     // Create action model
     let ui_actions = Rc::new(VecModel::<ActionItem>::default());
-    ui_actions.set_vec(all_actions.clone());
+    ui_actions.set_vec(history.borrow().sorted(&all_actions));
     ui.set_actions(ModelRc::from(ui_actions.clone()));
 
     let ui_handle = ui.as_weak();
-    let master_list = all_actions.clone();
+    let master_list = Rc::new(RefCell::new(all_actions));
     let display_model = ui_actions.clone();
 
-    // Handle action clicks
-    let actions_clone = ui_actions.clone();
+    // In daemon mode the window is hidden instead of exiting, so everything above stays loaded
+    let win_handle = ui.get_handler();
+    let visible = Rc::new(Cell::new(!daemon));
+    if daemon {
+        win_handle.hide();
+
+        ui.on_show_requested({
+            let ui_weak = ui.as_weak();
+            let win_handle = win_handle.clone();
+            let visible = visible.clone();
+            move || show(&ui_weak, &win_handle, &visible)
+        });
+        ui.on_hide_requested({
+            let ui_weak = ui.as_weak();
+            let win_handle = win_handle.clone();
+            let visible = visible.clone();
+            move || hide(&ui_weak, &win_handle, &visible)
+        });
+        ui.on_toggle_requested({
+            let ui_weak = ui.as_weak();
+            let win_handle = win_handle.clone();
+            let visible = visible.clone();
+            move || {
+                if visible.get() {
+                    hide(&ui_weak, &win_handle, &visible);
+                } else {
+                    show(&ui_weak, &win_handle, &visible);
+                }
+            }
+        });
+
+        // The window handle belongs to the event loop, so the socket thread only forwards
+        let ui_weak = ui.as_weak();
+        let plugins = plugins.clone();
+        ipc::listen(move |request| match request {
+            ipc::Request::Show => {
+                let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_show_requested());
+            }
+            ipc::Request::Hide => {
+                let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_hide_requested());
+            }
+            ipc::Request::Toggle => {
+                let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_toggle_requested());
+            }
+            ipc::Request::Reload => {
+                let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_reload());
+            }
+            ipc::Request::Quit => {
+                plugins.shutdown();
+                ipc::cleanup();
+                let _ = slint::quit_event_loop();
+                std::process::exit(0);
+            }
+        })?;
+    }
+
+    let dismiss: Rc<dyn Fn()> = {
+        let ui_weak = ui.as_weak();
+        let win_handle = win_handle.clone();
+        let visible = visible.clone();
+        let plugins = plugins.clone();
+        Rc::new(move || {
+            if daemon {
+                hide(&ui_weak, &win_handle, &visible);
+            } else {
+                plugins.shutdown();
+                let _ = slint::quit_event_loop();
+
+                // Force quit in case slint::quit_event_loop() fails
+                std::process::exit(0);
+            }
+        })
+    };
+
+    ui.on_reload({
+        let master_list = master_list.clone();
+        let launcher = launcher.clone();
+        let plugins = plugins.clone();
+        let ui_weak = ui.as_weak();
+        move || {
+            println!("Reloading");
+            let Some(ui) = ui_weak.upgrade() else {
+                return;
+            };
+            let _ = theme::apply_theme(&ui);
+            PLUGIN_ICONS.with_borrow_mut(HashMap::clear);
+            *master_list.borrow_mut() = load_actions();
+            *launcher.borrow_mut() = exec::Launcher::new(&config::load_settings());
+            load_plugins(&plugins, ui.as_weak());
+            ui.invoke_reset();
+        }
+    });
+
+    // A click selects the row and accepts it like Enter would
+    let click_ui = ui.as_weak();
     ui.on_action_clicked(move |idx| {
-        if let Some(action) = actions_clone.row_data(idx as usize) {
-            println!("Executing: {} - {}", action.name, action.exec);
-            let _foo = Command::new("sh")
-                .arg("-c")
-                .arg(action.exec.as_str())
-                .spawn();
+        if let Some(ui) = click_ui.upgrade() {
+            ui.set_selected(idx);
+            ui.invoke_accepted();
         }
     });
 
+    // Approval row the user pressed Enter on once, granting takes a second Enter so the
+    // default selection never hands a plugin its permissions
+    let pending_approval: Rc<RefCell<Option<slint::SharedString>>> = Rc::new(RefCell::new(None));
+
     let matcher = SkimMatcherV2::default();
+    let search_pending_approval = pending_approval.clone();
+    let search_history = history.clone();
+    let search_plugins = plugins.clone();
+    let search_ui = ui.as_weak();
     ui.on_search_changed(move |text: slint::SharedString| {
         let query = text.as_str().trim();
+        search_pending_approval.replace(None);
 
+        let master_list = master_list.borrow();
         if query.is_empty() {
-            display_model.set_vec(master_list.clone());
+            search_plugins.cancel();
+
+            // Plugins waiting for approval or failing to load come before anything else
+            let mut items: Vec<ActionItem> = search_plugins
+                .notices()
+                .into_iter()
+                .map(|item| ActionItem { icon: load_icon(&item), ..item })
+                .collect();
+            items.extend(search_history.borrow().sorted(&master_list));
+            display_model.set_vec(items);
             return;
         }
         println!("Search changed!");
 
-        if let Some(first_char) = query.chars().next()
-            && let Ok(mg) = manager.try_lock()
-        {
-            if let Some(res) = mg.run_trigger(first_char, query) {
-                let items: Vec<ActionItem> = res
+        if let Some(trigger) = search_plugins.trigger_for(query) {
+            // Stays up until the plugin answers, a newer keystroke replaces it
+            display_model.set_vec(vec![ActionItem {
+                name: "Loading…".into(),
+                kind: ActionKind::None,
+                ..Default::default()
+            }]);
+
+            // Streamed batches and the final reply alike, the first replaces the loading row and
+            // the rest are appended. A newer keystroke stops the stream
+            let ui_weak = search_ui.clone();
+            let shown = Arc::new(AtomicBool::new(false));
+            let show_reply = move |reply: plugins::Reply| {
+                let Some(ui) = ui_weak.upgrade() else {
+                    return;
+                };
+                let items: Vec<ActionItem> = reply
+                    .into_action_items()
                     .into_iter()
-                    .map(|item| ActionItem {
-                        name: item.name.into(),
-                        exec: item.exec.into(),
-                        keywords: item.keywords.into(),
-                        icon: Default::default(),
-                    })
+                    .map(with_plugin_icon)
                     .collect();
-                display_model.set_vec(items);
-            } else {
-                let mut filtered: Vec<(i64, ActionItem)> = master_list
-                    .iter()
-                    .filter_map(|item| {
-                        let score = matcher
-                            .fuzzy_match(&item.name, &text)
-                            .or_else(|| matcher.fuzzy_match(&item.keywords, &text))
-                            .or_else(|| matcher.fuzzy_match(&item.exec, &text));
-
-                        score.map(|s| (s, item.clone()))
+                if shown.swap(true, Ordering::SeqCst) {
+                    append_actions(&ui, items);
+                } else {
+                    set_actions(&ui, items);
+                }
+            };
+            search_plugins.query(trigger, query.to_string(), show_reply.clone(), show_reply);
+        } else {
+            let history = search_history.borrow();
+            let mut filtered: Vec<(i64, ActionItem)> = master_list
+                .iter()
+                .filter_map(|item| {
+                    let score = matcher
+                        .fuzzy_match(&item.name, &text)
+                        .or_else(|| matcher.fuzzy_match(&item.untranslated_name, &text))
+                        .or_else(|| matcher.fuzzy_match(&item.generic_name, &text))
+                        .or_else(|| matcher.fuzzy_match(&item.keywords, &text))
+                        .or_else(|| matcher.fuzzy_match(&item.categories, &text))
+                        .or_else(|| matcher.fuzzy_match(&item.comment, &text))
+                        .or_else(|| matcher.fuzzy_match(&item.exec, &text));
+
+                    score.map(|s| {
+                        let score = history.blend(s, item);
+                        (score, ActionItem { score: score as i32, ..item.clone() })
                     })
+                })
+                .collect();
+
+            filtered.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+            let new_model: Vec<ActionItem> =
+                filtered.into_iter().enumerate().map(|(i, (_, item))|{
+                    let time = std::time::Instant::now();
+                    let icon = if !item.icon.size().is_empty() {
+                        item.icon.clone()
+                    } else if i < 5 {
+                        println!("loading icon {}, at index {}", item.name.as_str(), i);
+                        load_icon(&item)
+                    } else {
+                        Default::default()
+                    };
+
+                    let elapsed = time.elapsed();
+                    println!("Time took to find icons: {:.2?}", elapsed);
+
+                    ActionItem { icon, ..item }
+                }).collect();
+            display_model.set_vec(new_model);
+
+            // Global plugins answer later and get merged into the apps shown by then
+            let ui_weak = search_ui.clone();
+            search_plugins.query_global(query.to_string(), move |replies| {
+                let Some(ui) = ui_weak.upgrade() else {
+                    return;
+                };
+                let mut items: Vec<ActionItem> = ui
+                    .get_actions()
+                    .iter()
+                    .filter(|item| item.provider.is_empty())
                     .collect();
+                items.extend(
+                    replies
+                        .into_iter()
+                        .flat_map(plugins::Reply::into_action_items)
+                        .map(with_plugin_icon),
+                );
+                // Stable, so apps stay ahead of plugin results with the same score
+                items.sort_by_key(|item| std::cmp::Reverse(item.score));
+                set_actions(&ui, items);
+            });
+        }
+    });
 
-                filtered.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
-
-                let new_model: Vec<ActionItem> =
-                    filtered.into_iter().enumerate().map(|(i, (_, item))|{
-                        let time = std::time::Instant::now();
-                        let icon = if !item.icon.size().is_empty() {
-                            item.icon.clone()
-                        } else if i < 5 {
-                            println!("loading icon {}, at index {}", item.name.as_str(), i);
-
-                            if let Some(ico_path) = find_icon(&item.name.as_str().to_lowercase(), 256) {
-                                println!("path: {:?}", ico_path);
-                                slint::Image::load_from_path(&ico_path).unwrap_or_default()
-                            } else {
-                                println!("Failed to find icon");
-                                Default::default()
-                            }
-                        } else {
-                            Default::default()
-                        };
-
-                        let elapsed = time.elapsed();
-                        println!("Time took to find icons: {:.2?}", elapsed);
-
-                        ActionItem {
-                            name: item.name.into(),
-                            exec: item.exec.into(),
-                            keywords: item.keywords.into(),
-                            icon,
-                        }
-                    }).collect();
-                display_model.set_vec(new_model);
+    ui.on_preview_requested({
+        let plugins = plugins.clone();
+        let ui_weak = ui.as_weak();
+        move |provider, item_id| {
+            if item_id.is_empty() {
+                plugins.cancel_preview();
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_preview(Preview::default());
+                }
+                return;
             }
+
+            // The previous preview stays up until this one arrives, so the pane doesn't flicker
+            let ui_weak = ui_weak.clone();
+            plugins.preview(provider.to_string(), item_id.to_string(), move |content| {
+                if let Some(ui) = ui_weak.upgrade() {
+                    ui.set_preview(to_preview(content));
+                }
+            });
         }
     });
 
-    ui.on_linefinished(move |app| {
-        let _foo = Command::new("sh").arg("-c").arg(app.as_str()).spawn();
-        // slint::quit_event_loop();
-
-        // Force quit in case slint::quit_event_loop() fails
-        std::process::exit(0);
+    ui.on_linefinished({
+        let launcher = launcher.clone();
+        let dismiss = dismiss.clone();
+        move |app| {
+            // Typed straight into the runner, so treat it like a terminal would
+            launch(
+                &launcher.borrow(),
+                &ActionItem {
+                    name: app.clone(),
+                    exec: app,
+                    kind: ActionKind::Shell,
+                    ..Default::default()
+                },
+            );
+            dismiss();
+        }
     });
 
     ui.on_accepted({
         let ui_actions_clone = ui_actions.clone();
+        let plugins = plugins.clone();
+        let history = history.clone();
+        let launcher = launcher.clone();
+        let dismiss = dismiss.clone();
 
         move || {
             let ui = ui_handle.unwrap();
             let selected = ui.get_selected();
             if let Some(first_item) = ui_actions_clone.row_data(selected.try_into().unwrap()) {
-                println!("Launching: {}", first_item.name);
-
-                let _ = Command::new("sh").arg("-c").arg(&first_item.exec).spawn();
+                if first_item.kind == ActionKind::AllowPlugin {
+                    // The first Enter only asks for a second one
+                    if pending_approval.replace(None).as_ref() != Some(&first_item.exec) {
+                        pending_approval.replace(Some(first_item.exec.clone()));
+                        let name = format!("Press Enter again to allow plugin {}", first_item.exec);
+                        let confirm = ActionItem { name: name.into(), ..first_item };
+                        ui_actions_clone.set_row_data(selected as usize, confirm);
+                        return;
+                    }
+
+                    if let Err(e) = plugins.approve(&first_item.exec) {
+                        eprintln!("Failed to approve {}: {}", first_item.exec, e);
+                    }
+                    ui.invoke_refresh();
+                    return;
+                }
 
-                let _ = slint::quit_event_loop();
-
-                std::process::exit(0);
+                println!("Launching: {}", first_item.name);
+                // Only apps and config entries, plugin rows never come back through `master_list`
+                // and a copy action's exec is the copied text itself
+                if first_item.kind != ActionKind::None && first_item.provider.is_empty() {
+                    history.borrow_mut().record(&first_item);
+                }
+
+                launch(&launcher.borrow(), &first_item);
+                if !first_item.item_id.is_empty() {
+                    plugins.activate(first_item.provider.to_string(), first_item.item_id.to_string());
+                }
+
+                // Informational rows keep the launcher open
+                if first_item.kind != ActionKind::None {
+                    dismiss();
+                }
             }
         }
     });
 
     ui.on_quit(move || {
-        std::process::exit(0);
+        dismiss();
     });
 
     cast_spell!(ui)
 }
+
+fn launch(launcher: &exec::Launcher, action: &ActionItem) {
+    if let Err(e) = launcher.launch(action) {
+        eprintln!("Failed to launch {}: {}", action.name, e);
+    }
+}
+
+/// Looks up the item's icon name (or absolute path), falling back to its lowercased name.
+fn load_icon(item: &ActionItem) -> slint::Image {
+    if item.icon_name.starts_with('/') {
+        return slint::Image::load_from_path(std::path::Path::new(item.icon_name.as_str()))
+            .unwrap_or_default();
+    }
+
+    let icon_name = if item.icon_name.is_empty() {
+        item.name.as_str().to_lowercase()
+    } else {
+        item.icon_name.to_string()
+    };
+
+    if let Some(ico_path) = find_icon(&icon_name, 256) {
+        println!("path: {:?}", ico_path);
+        slint::Image::load_from_path(&ico_path).unwrap_or_default()
+    } else {
+        println!("Failed to find icon");
+        Default::default()
+    }
+}
+
+fn load_actions() -> Vec<ActionItem> {
+    let config = config::load_config();
+    let mut all_actions = scraper::get_programs();
+    all_actions.extend(config);
+    all_actions
+}
+
+fn load_plugins(plugins: &plugins::Plugins, ui_weak: slint::Weak<LauncherWindow>) {
+    plugins.reload(move || {
+        // Shows pending approvals and plugin results the query missed while loading
+        let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_refresh());
+    });
+}
+
+thread_local! {
+    // Plugin icons by name, including the ones that weren't found. A streamed listing asks for
+    // the same few for every row, and rows are only built on the UI thread
+    static PLUGIN_ICONS: RefCell<HashMap<slint::SharedString, slint::Image>> = RefCell::default();
+}
+
+/// Plugins only get an icon when they ask for one, each name is looked up once.
+fn with_plugin_icon(item: ActionItem) -> ActionItem {
+    if item.icon_name.is_empty() {
+        return item;
+    }
+    let icon = PLUGIN_ICONS.with_borrow_mut(|icons| {
+        icons
+            .entry(item.icon_name.clone())
+            .or_insert_with(|| load_icon(&item))
+            .clone()
+    });
+    ActionItem { icon, ..item }
+}
+
+/// Built on the UI thread, like rows the preview may hold an image.
+fn to_preview(content: plugins::PreviewContent) -> Preview {
+    match content {
+        plugins::PreviewContent::Text(text) => Preview {
+            kind: PreviewKind::Text,
+            text: text.into(),
+            ..Default::default()
+        },
+        plugins::PreviewContent::Image(path) => {
+            match slint::Image::load_from_path(std::path::Path::new(&path)) {
+                Ok(image) => Preview {
+                    kind: PreviewKind::Image,
+                    image,
+                    ..Default::default()
+                },
+                Err(_) => Preview::default(),
+            }
+        }
+        plugins::PreviewContent::Details(details) => Preview {
+            kind: PreviewKind::Details,
+            details: ModelRc::new(VecModel::from(
+                details
+                    .into_iter()
+                    .map(|(label, value)| PreviewDetail {
+                        label: label.into(),
+                        value: value.into(),
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+            ..Default::default()
+        },
+        plugins::PreviewContent::None => Preview::default(),
+    }
+}
+
+/// Replaces the shown results from code that only has the window, not the model.
+fn set_actions(ui: &LauncherWindow, items: Vec<ActionItem>) {
+    let actions = ui.get_actions();
+    if let Some(model) = actions.as_any().downcast_ref::<VecModel<ActionItem>>() {
+        model.set_vec(items);
+    }
+}
+
+/// Adds rows below the shown results, e.g. a batch a plugin streamed.
+fn append_actions(ui: &LauncherWindow, items: Vec<ActionItem>) {
+    let actions = ui.get_actions();
+    if let Some(model) = actions.as_any().downcast_ref::<VecModel<ActionItem>>() {
+        model.extend(items);
+    }
+}
+
+fn show(
+    ui_weak: &slint::Weak<LauncherWindow>,
+    win_handle: &spell_framework::wayland_adapter::WinHandle,
+    visible: &Cell<bool>,
+) {
+    let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_reset());
+    win_handle.show_again();
+    visible.set(true);
+}
+
+fn hide(
+    ui_weak: &slint::Weak<LauncherWindow>,
+    win_handle: &spell_framework::wayland_adapter::WinHandle,
+    visible: &Cell<bool>,
+) {
+    win_handle.hide();
+    visible.set(false);
+    let _ = ui_weak.upgrade_in_event_loop(|ui| ui.invoke_reset());
+}
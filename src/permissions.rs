@@ -0,0 +1,175 @@
+use ini::Ini;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::path::PathBuf;
+
+use wasmparser::{Parser, Payload};
+
+/// Custom section a plugin can embed its manifest in instead of shipping a sidecar file
+pub const CUSTOM_SECTION: &str = "swift-permissions";
+
+/// What a plugin asks for in its manifest, everything else stays out of its sandbox.
+///
+/// ```toml
+/// filesystem = ["~/Documents", "/config"]
+/// network = ["api.github.com", "*.wikipedia.org"]
+/// interfaces = ["clipboard", "http"]
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Permissions {
+    /// Paths mounted read-only at the same place, `/config` is the swift config directory
+    pub filesystem: Vec<String>,
+    /// Hosts reachable over sockets and wasi-http, `*.` matches subdomains
+    pub network: Vec<String>,
+    /// Host interfaces: `clipboard`, `http`, and the actions that run something on the host:
+    /// `spawn`, `shell`, `open-uri`
+    pub interfaces: Vec<String>,
+}
+
+impl Permissions {
+    /// Reads the sidecar `<plugin>.toml` if there is one, else the manifest embedded in the component.
+    /// Plugins without either get an empty sandbox.
+    pub fn find(sidecar: Option<&str>, wasm: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if let Some(manifest) = sidecar {
+            return Ok(toml::from_str(manifest)?);
+        }
+
+        for payload in Parser::new(0).parse_all(wasm) {
+            if let Payload::CustomSection(section) = payload?
+                && section.name() == CUSTOM_SECTION
+            {
+                return Ok(toml::from_str(std::str::from_utf8(section.data())?)?);
+            }
+        }
+
+        Ok(Self::default())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filesystem.is_empty() && self.network.is_empty() && self.interfaces.is_empty()
+    }
+
+    pub fn allows_interface(&self, interface: &str) -> bool {
+        self.interfaces.iter().any(|i| i == interface)
+    }
+
+    pub fn allows_host(&self, host: &str) -> bool {
+        self.network.iter().any(|allowed| match allowed.strip_prefix("*.") {
+            Some(domain) => host == domain || host.ends_with(&format!(".{}", domain)),
+            None => host == allowed,
+        })
+    }
+
+    /// Whether everything `requested` asks for was already granted here.
+    pub fn covers(&self, requested: &Permissions) -> bool {
+        let all = |granted: &[String], asked: &[String]| asked.iter().all(|a| granted.contains(a));
+        all(&self.filesystem, &requested.filesystem)
+            && all(&self.network, &requested.network)
+            && all(&self.interfaces, &requested.interfaces)
+    }
+
+    /// Host and guest path for every readable directory, with `~` and `/config` expanded.
+    pub fn mounts(&self) -> Vec<(String, String)> {
+        let home = std::env::var("HOME").unwrap_or_default();
+        self.filesystem
+            .iter()
+            .map(|path| {
+                if path == "/config" {
+                    (format!("{}/.config/swift", home), path.clone())
+                } else {
+                    let host = match path.strip_prefix('~') {
+                        Some(rest) => format!("{}{}", home, rest),
+                        None => path.clone(),
+                    };
+                    (host.clone(), host)
+                }
+            })
+            .collect()
+    }
+
+    /// Addresses the listed hosts resolve to right now, wildcards can't be resolved
+    /// and only work through wasi-http.
+    pub fn resolve_hosts(&self) -> Vec<IpAddr> {
+        self.network
+            .iter()
+            .filter(|host| !host.starts_with("*."))
+            .filter_map(|host| (host.as_str(), 0).to_socket_addrs().ok())
+            .flatten()
+            .map(|addr| addr.ip())
+            .collect()
+    }
+
+    /// One line summary shown when asking for approval.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.filesystem.is_empty() {
+            parts.push(format!("Read {}", self.filesystem.join(", ")));
+        }
+        if !self.network.is_empty() {
+            parts.push(format!("Connect to {}", self.network.join(", ")));
+        }
+        if !self.interfaces.is_empty() {
+            parts.push(format!("Use {}", self.interfaces.join(", ")));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Permissions the user approved for plugin `id`, as stored in `permissions.conf`.
+/// An approval only holds for the component it was given to, `digest` is its current one.
+pub fn load_approved(id: &str, digest: &str) -> Option<Permissions> {
+    let conf = Ini::load_from_file(get_permissions_file()).ok()?;
+    let section = conf.section(Some(id))?;
+    if section.get("sha256") != Some(digest) {
+        return None;
+    }
+    let list = |key: &str| -> Vec<String> {
+        section
+            .get(key)
+            .unwrap_or("")
+            .split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    Some(Permissions {
+        filesystem: list("filesystem"),
+        network: list("network"),
+        interfaces: list("interfaces"),
+    })
+}
+
+pub fn approve(id: &str, digest: &str, permissions: &Permissions) -> Result<(), Box<dyn Error>> {
+    let path = get_permissions_file();
+    let mut conf = Ini::load_from_file(&path).unwrap_or_default();
+    conf.with_section(Some(id))
+        .set("sha256", digest)
+        .set("filesystem", permissions.filesystem.join(";"))
+        .set("network", permissions.network.join(";"))
+        .set("interfaces", permissions.interfaces.join(";"));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    conf.write_to_file(&path)?;
+    Ok(())
+}
+
+/// SHA-256 of a component in hex, what an approval is tied to. Replacing the file under the
+/// same name asks again.
+pub fn digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn get_permissions_file() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_default();
+    PathBuf::from(format!("{}/.config/swift/permissions.conf", home))
+}
@@ -0,0 +1,279 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::config;
+use crate::ipc;
+use crate::plugins::{self, PluginInfo, PluginManager};
+use crate::storage;
+
+const USAGE: &str = "Usage: Swift-launcher plugins <command>
+  list                 Every plugin with its trigger and status
+  info <id>            Where it comes from, its permissions and why it failed to load
+  enable <id>          Turn a plugin back on
+  disable <id>         Turn a plugin off without removing it
+  install <file.wasm>  Check a plugin and copy it, with its .toml manifest, to the plugin directory
+  remove <id>          Delete an installed plugin
+  extract <id>         Copy a built-in to the plugin directory, where it replaces the built-in
+  wipe <id>            Delete everything a plugin stored";
+
+/// `plugins <command>`, managing plugins without starting the launcher.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["list"] => list(),
+        ["info", id] => info(id),
+        ["enable", id] => set_enabled(id, true),
+        ["disable", id] => set_enabled(id, false),
+        ["install", file] => install(Path::new(file)),
+        ["remove", id] => remove(id),
+        ["extract", id] => extract(id),
+        ["wipe", id] => wipe(id),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn list() -> Result<(), Box<dyn Error>> {
+    let infos = load()?.infos();
+    let width = infos
+        .iter()
+        .map(|info| info.id.len())
+        .max()
+        .unwrap_or(0)
+        .max(2);
+
+    println!("{:width$}  {:8}  {:8}  STATUS", "ID", "TRIGGER", "SOURCE");
+    for info in &infos {
+        println!(
+            "{:width$}  {:8}  {:8}  {}",
+            info.id,
+            info.trigger.as_deref().unwrap_or("-"),
+            if info.builtin { "built-in" } else { "user" },
+            status(info)
+        );
+    }
+    Ok(())
+}
+
+fn info(id: &str) -> Result<(), Box<dyn Error>> {
+    let infos = load()?.infos();
+    let matching: Vec<&PluginInfo> = infos.iter().filter(|info| info.id == id).collect();
+    if matching.is_empty() {
+        return Err(format!("No plugin {}, see `plugins list`", id).into());
+    }
+
+    for info in matching {
+        println!("{}", info.id);
+        if info.builtin {
+            println!("  Source:       built-in");
+        } else {
+            println!("  Source:       {}", info.path.display());
+        }
+        println!("  Status:       {}", status(info));
+        if info.overridden {
+            println!("  Replaced by:  {}", info.path.display());
+        }
+        if let Some(error) = &info.error {
+            println!("  Error:        {}", error);
+            continue;
+        }
+        if !info.enabled {
+            continue;
+        }
+
+        match (&info.trigger, &info.native_trigger) {
+            (Some(trigger), Some(native)) => {
+                println!(
+                    "  Trigger:      {:?} (the plugin asks for {:?})",
+                    trigger, native
+                )
+            }
+            (Some(trigger), None) => println!("  Trigger:      {:?}", trigger),
+            (None, _) => println!("  Trigger:      none"),
+        }
+        println!("  Global:       {}", if info.global { "yes" } else { "no" });
+        println!(
+            "  Interface:    {}",
+            if info.legacy {
+                "legacy (plugin-v1.wit)"
+            } else {
+                "swift:launcher@0.2.0"
+            }
+        );
+        println!(
+            "  Cache:        {}",
+            if info.cached {
+                "compiled"
+            } else {
+                "not cached, compiled on every start"
+            }
+        );
+        let permissions = info.permissions.clone().unwrap_or_default();
+        if permissions.is_empty() {
+            println!("  Permissions:  none");
+        } else {
+            println!("  Permissions:  {}", permissions.describe());
+            println!(
+                "  Approved:     {}",
+                if info.approved {
+                    "yes"
+                } else {
+                    "no, approve it in the launcher"
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
+fn set_enabled(id: &str, enabled: bool) -> Result<(), Box<dyn Error>> {
+    if !load()?.infos().iter().any(|info| info.id == id) {
+        return Err(format!("No plugin {}, see `plugins list`", id).into());
+    }
+
+    config::set_plugin_value(id, "enabled", if enabled { "true" } else { "false" })?;
+    println!("{} {}", if enabled { "Enabled" } else { "Disabled" }, id);
+    notify_daemon();
+    Ok(())
+}
+
+fn install(file: &Path) -> Result<(), Box<dyn Error>> {
+    if file.extension().and_then(|s| s.to_str()) != Some("wasm") {
+        return Err(format!("{} is not a .wasm file", file.display()).into());
+    }
+
+    // Loaded next to everything installed, so a clashing trigger fails here and not later
+    let info = load()?
+        .check(file)
+        .map_err(|e| format!("{} can't be installed: {}", file.display(), e))?;
+
+    let dir = plugins::user_dir();
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(format!("{}.wasm", info.id));
+    let manifest = file.with_extension("toml");
+    // An old manifest would hand the new plugin permissions it never asked for
+    if manifest.exists() {
+        fs::copy(&manifest, target.with_extension("toml"))?;
+    } else {
+        let _ = fs::remove_file(target.with_extension("toml"));
+    }
+    // Copied last, the running launcher picks the plugin up as soon as it's complete
+    fs::copy(file, &target)?;
+
+    println!(
+        "Installed {} as {}, trigger {}",
+        info.id,
+        target.display(),
+        info.trigger
+            .as_deref()
+            .map_or("none".to_string(), |trigger| format!("{:?}", trigger))
+    );
+    if let Some(permissions) = info.permissions.filter(|p| !p.is_empty())
+        && !info.approved
+    {
+        println!(
+            "It asks to {}, approve it in the launcher",
+            permissions.describe()
+        );
+    }
+    Ok(())
+}
+
+fn remove(id: &str) -> Result<(), Box<dyn Error>> {
+    let target = plugins::user_dir().join(format!("{}.wasm", id));
+    if !target.exists() {
+        return match load()?.infos().iter().find(|info| info.id == id) {
+            Some(info) if info.builtin => Err(format!(
+                "{} is built in, turn it off with `plugins disable {}`",
+                id, id
+            )
+            .into()),
+            _ => Err(format!("No installed plugin {}", id).into()),
+        };
+    }
+
+    fs::remove_file(&target)?;
+    let _ = fs::remove_file(target.with_extension("toml"));
+    println!("Removed {}", id);
+    if plugins::builtin_source(id).is_some() {
+        println!("The built-in {} is used again", id);
+    }
+    if storage::data_dir(id).exists() {
+        println!("Its data is kept, `plugins wipe {}` deletes it", id);
+    }
+    Ok(())
+}
+
+fn extract(id: &str) -> Result<(), Box<dyn Error>> {
+    let (wasm, manifest) =
+        plugins::builtin_source(id).ok_or_else(|| format!("No built-in plugin {}", id))?;
+    let dir = plugins::user_dir();
+    let target = dir.join(format!("{}.wasm", id));
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+
+    fs::create_dir_all(&dir)?;
+    if let Some(manifest) = manifest {
+        fs::write(target.with_extension("toml"), manifest)?;
+    }
+    fs::write(&target, wasm)?;
+    println!(
+        "Extracted {} to {}, it replaces the built-in",
+        id,
+        target.display()
+    );
+    if manifest.is_some() {
+        println!(
+            "Its permissions are in {}",
+            target.with_extension("toml").display()
+        );
+    }
+    println!("`plugins remove {}` goes back to the built-in", id);
+    Ok(())
+}
+
+fn wipe(id: &str) -> Result<(), Box<dyn Error>> {
+    storage::wipe(id)?;
+    // A running instance still has the deleted directory open, reloading starts it over
+    notify_daemon();
+    Ok(())
+}
+
+fn load() -> Result<PluginManager, Box<dyn Error>> {
+    let mut manager = PluginManager::new();
+    manager.load_all()?;
+    Ok(manager)
+}
+
+fn status(info: &PluginInfo) -> String {
+    if info.overridden {
+        return "overridden".to_string();
+    }
+    if info.error.is_some() {
+        return "failed".to_string();
+    }
+    if !info.enabled {
+        return "disabled".to_string();
+    }
+
+    let mut status = vec!["enabled"];
+    if info.global {
+        status.push("global");
+    }
+    if !info.approved {
+        status.push("needs approval");
+    }
+    status.join(", ")
+}
+
+/// Lets a running daemon pick up the change, without one there's nothing to do.
+fn notify_daemon() {
+    if ipc::send(ipc::Request::Reload).is_ok() {
+        println!("Reloaded the running launcher");
+    }
+}
@@ -1,16 +1,56 @@
 use include_dir::{Dir, include_dir};
-use std::collections::HashMap;
-use std::env;
+use inotify::{Events, Inotify, WatchMask};
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::IpAddr;
 use std::option::Option;
-use std::path::PathBuf;
-use wasmtime::component::{Component, Linker, ResourceTable, bindgen};
-use wasmtime::{Config, Engine, Store};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::Duration;
+use wasmtime::component::{Component, HasSelf, Linker, Resource, ResourceTable, bindgen};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder, Trap, UpdateDeadline};
 use wasmtime_wasi::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
-use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
+use wasmtime_wasi_http::bindings::http::types::ErrorCode;
+use wasmtime_wasi_http::body::HyperOutgoingBody;
+use wasmtime_wasi_http::types::{
+    HostFutureIncomingResponse, OutgoingRequestConfig, default_send_request,
+};
+use wasmtime_wasi_http::{HttpResult, WasiHttpCtx, WasiHttpView};
 
-use rayon::current_thread_index;
+use crate::cache;
+use crate::clipboard;
+use crate::config::{self, PluginSettings};
+use crate::exec::{FieldCodes, parse_exec, quote_argv};
+use crate::permissions::{self, Permissions};
+use crate::storage;
+use crate::{ActionItem, ActionKind};
+use exports::swift::launcher::runner;
+use swift::launcher::types::Action;
 
-bindgen!({ world: "plugin-world", path: "plugin.wit" });
+bindgen!({
+    world: "plugin-world",
+    path: "plugin.wit",
+    with: { "swift:launcher/results.result-sink": ResultSink },
+});
+
+pub use runner::PreviewContent;
+
+/// Plugins built against the original unversioned interface
+mod legacy {
+    wasmtime::component::bindgen!({ world: "plugin-world", path: "plugin-v1.wit" });
+}
+
+// How often the engine's epoch advances while a plugin runs, the granularity of plugin timeouts
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+// Copying or saving a plugin takes several writes, changes are picked up once they stop
+const SETTLE_TIME: Duration = Duration::from_millis(250);
+// A good fuzzy name match scores about this, so a global result scored 1.0 ranks alongside it
+const GLOBAL_SCORE_WEIGHT: f32 = 100.0;
+// Characters of preview text shown, the pane has room for far less anyway
+const PREVIEW_TEXT_LIMIT: usize = 8192;
+// Exported by every plugin built against `plugin.wit`
+const RUNNER_INTERFACE: &str = "swift:launcher/runner@0.2.0";
 
 static BUILTIN_PLUGINS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/built_in_plugins");
 
@@ -18,11 +58,741 @@ pub struct MyState {
     wasi: WasiCtx,
     http_ctx: WasiHttpCtx,
     table: ResourceTable,
+    permissions: Permissions,
+    limits: StoreLimits,
+    // Set by every batch a streaming query pushes, each one buys the plugin another timeout
+    pushed: bool,
 }
 pub struct PluginManager {
     engine: Engine,
     linker: Linker<MyState>,
-    plugins: HashMap<char, Component>,
+    ticker: Ticker,
+    plugins: Vec<Plugin>,
+    // Shown in the result list, since nobody reads stderr of a launcher. Keyed by plugin id,
+    // so fixing a plugin clears its error
+    load_errors: Vec<(String, String)>,
+    // Turned off in config.conf, known only so they can be listed
+    disabled: Vec<Disabled>,
+}
+
+struct Disabled {
+    id: String,
+    path: PathBuf,
+    builtin: bool,
+}
+
+/// What `plugins list` and `plugins info` report about a plugin.
+#[derive(Default)]
+pub struct PluginInfo {
+    pub id: String,
+    /// Where the `.wasm` lives, for built-ins the path a user copy would override
+    pub path: PathBuf,
+    pub builtin: bool,
+    pub enabled: bool,
+    /// The trigger in effect, and the one the plugin asked for if the user changed it
+    pub trigger: Option<String>,
+    pub native_trigger: Option<String>,
+    pub global: bool,
+    pub legacy: bool,
+    /// Whether the compiled component is in the cache, so the next start skips compiling
+    pub cached: bool,
+    pub permissions: Option<Permissions>,
+    pub approved: bool,
+    /// Why it failed to compile or register
+    pub error: Option<String>,
+    /// A built-in replaced by the user plugin of the same id
+    pub overridden: bool,
+}
+
+/// A plugin ready to register, compiled without holding the manager.
+struct Compiled {
+    id: String,
+    path: PathBuf,
+    component: Component,
+    permissions: Permissions,
+    builtin: bool,
+    // Its entry in the compiled component cache
+    key: String,
+    // Hash of the component, approvals are tied to it
+    digest: String,
+}
+
+/// Plugin results as they leave the sandbox. They only become rows on the UI thread,
+/// since `ActionItem` holds an image and can't be sent across threads.
+pub enum Reply {
+    Items {
+        id: String,
+        items: Vec<runner::ActionItem>,
+    },
+    /// The plugin doesn't run until the user approves what its manifest asks for
+    Approval { id: String, permissions: String },
+    /// The plugin trapped or ran into one of its limits and was stopped
+    Failed { id: String, reason: String },
+}
+
+impl Reply {
+    pub fn into_action_items(self) -> Vec<ActionItem> {
+        match self {
+            Reply::Items { id, items } => items
+                .into_iter()
+                .map(|item| {
+                    // Unscored items go below every app when mixed into a search. Scores are
+                    // 0.0 to 1.0, anything above can't outrank a good app match
+                    let score = item
+                        .score
+                        .filter(|score| !score.is_nan())
+                        .map_or(i32::MIN, |score| {
+                            (score.clamp(0.0, 1.0) * GLOBAL_SCORE_WEIGHT) as i32
+                        });
+                    ActionItem {
+                        score,
+                        provider: id.as_str().into(),
+                        ..to_action_item(item)
+                    }
+                })
+                .collect(),
+            Reply::Approval { id, permissions } => vec![approval_item(&id, &permissions)],
+            Reply::Failed { id, reason } => vec![ActionItem {
+                name: format!("Plugin {} was stopped", id).into(),
+                comment: reason.into(),
+                icon_name: "dialog-error".into(),
+                kind: ActionKind::None,
+                provider: id.into(),
+                ..Default::default()
+            }],
+        }
+    }
+}
+
+/// Whether the query it was handed to is still the latest one.
+type IsCurrent = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Host side of a `result-sink`, forwards the batches of one streaming query.
+pub struct ResultSink {
+    // Plugin the batches come from, set once the query reaches it
+    provider: String,
+    is_current: IsCurrent,
+    on_batch: Box<dyn Fn(Reply) + Send + Sync>,
+}
+
+/// Runs plugin queries on the rayon pool so a slow plugin never blocks the UI.
+/// Every query bumps the generation, results of anything older are dropped.
+#[derive(Clone)]
+pub struct Plugins {
+    manager: Arc<Mutex<PluginManager>>,
+    generation: Arc<AtomicU64>,
+    // Readable while a query holds the manager
+    triggers: Arc<RwLock<HashSet<String>>>,
+    has_global: Arc<AtomicBool>,
+    // Picked items waiting to be reported to their plugin, see `activate`
+    activations: Arc<Mutex<Vec<(String, String)>>>,
+    // Like `generation` for previews, moving the selection doesn't drop query results
+    previews: Arc<AtomicU64>,
+}
+
+impl Plugins {
+    pub fn new() -> Self {
+        Self {
+            manager: Arc::new(Mutex::new(PluginManager::new())),
+            generation: Arc::new(AtomicU64::new(0)),
+            triggers: Arc::new(RwLock::new(HashSet::new())),
+            has_global: Arc::new(AtomicBool::new(false)),
+            activations: Arc::new(Mutex::new(Vec::new())),
+            previews: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Compiles plugins in the background and swaps them in once ready, then calls `on_loaded`.
+    pub fn reload<F: FnOnce() + Send + 'static>(&self, on_loaded: F) {
+        let plugins = self.clone();
+        rayon::spawn(move || {
+            let mut fresh = PluginManager::new();
+            if let Err(e) = fresh.load_all() {
+                eprintln!("Failed to load plugins: {}", e);
+            }
+            plugins.publish(&fresh);
+            let mut old = {
+                let mut manager = plugins.manager.lock().unwrap();
+                plugins.run_activations(&mut manager);
+                std::mem::replace(&mut *manager, fresh)
+            };
+            on_loaded();
+            old.shutdown();
+        });
+    }
+
+    /// The longest trigger `query` starts with, so `gh ` wins over `g`.
+    pub fn trigger_for(&self, query: &str) -> Option<String> {
+        self.triggers
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|trigger| query.starts_with(trigger.as_str()))
+            .max_by_key(|trigger| trigger.len())
+            .cloned()
+    }
+
+    /// Runs `input` through the plugin for `trigger`. The batches it streams go to `on_batch`
+    /// and its final reply to `on_reply`, both on the Slint event loop and only until another
+    /// query or `cancel` comes in. That also tells the plugin to stop.
+    pub fn query<B, F>(&self, trigger: String, input: String, on_batch: B, on_reply: F)
+    where
+        B: Fn(Reply) + Send + Sync + 'static,
+        F: FnOnce(Reply) + Send + 'static,
+    {
+        let on_batch = Arc::new(on_batch);
+        self.dispatch(
+            &self.generation,
+            move |manager, is_current| {
+                let sink = ResultSink {
+                    provider: String::new(),
+                    is_current: Arc::clone(&is_current),
+                    on_batch: Box::new(move |reply| {
+                        let on_batch = Arc::clone(&on_batch);
+                        let is_current = Arc::clone(&is_current);
+                        let _ = slint::invoke_from_event_loop(move || {
+                            if is_current() {
+                                on_batch(reply);
+                            }
+                        });
+                    }),
+                };
+                manager
+                    .run_trigger(&trigger, &input, Some(sink))
+                    .unwrap_or(Reply::Items {
+                        id: String::new(),
+                        items: Vec::new(),
+                    })
+            },
+            on_reply,
+        );
+    }
+
+    /// Sends a query without a trigger to every global plugin, in parallel. Also drops
+    /// results still on their way, so it stands in for `cancel` when there are none.
+    pub fn query_global<F>(&self, input: String, on_replies: F)
+    where
+        F: FnOnce(Vec<Reply>) + Send + 'static,
+    {
+        if !self.has_global.load(Ordering::SeqCst) {
+            self.cancel();
+            return;
+        }
+        self.dispatch(
+            &self.generation,
+            move |manager, _| manager.run_global(&input),
+            on_replies,
+        );
+    }
+
+    /// Asks the plugin that returned the selected item what to show next to it, and hands that
+    /// to `on_preview` on the Slint event loop unless the selection moved on first.
+    pub fn preview<F>(&self, provider: String, item_id: String, on_preview: F)
+    where
+        F: FnOnce(PreviewContent) + Send + 'static,
+    {
+        self.dispatch(
+            &self.previews,
+            move |manager, _| manager.preview(&provider, &item_id),
+            on_preview,
+        );
+    }
+
+    /// Drops the preview still on its way, e.g. once an item without one is selected.
+    pub fn cancel_preview(&self) {
+        self.previews.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn dispatch<R, Q, F>(&self, counter: &Arc<AtomicU64>, run: Q, on_reply: F)
+    where
+        R: Send + 'static,
+        Q: FnOnce(&mut PluginManager, IsCurrent) -> R + Send + 'static,
+        F: FnOnce(R) + Send + 'static,
+    {
+        let generation = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        let current = Arc::clone(counter);
+        let is_current: IsCurrent = Arc::new(move || current.load(Ordering::SeqCst) == generation);
+
+        let manager = Arc::clone(&self.manager);
+        rayon::spawn(move || {
+            // Skip keystrokes that were already typed over while waiting for the manager
+            if !is_current() {
+                return;
+            }
+            let mut manager = manager.lock().unwrap();
+            if !is_current() {
+                return;
+            }
+            let reply = run(&mut manager, Arc::clone(&is_current));
+            drop(manager);
+
+            let _ = slint::invoke_from_event_loop(move || {
+                if is_current() {
+                    on_reply(reply);
+                }
+            });
+        });
+    }
+
+    /// Watches the user plugin directory and swaps in every `.wasm` that is added, changed or
+    /// removed, then calls `on_changed`. Compile errors end up in `notices`.
+    pub fn watch<F: Fn() + Send + 'static>(&self, on_changed: F) -> Result<(), Box<dyn Error>> {
+        let dir = user_dir();
+        std::fs::create_dir_all(&dir)?;
+        let mut inotify = Inotify::init()?;
+        inotify.watches().add(
+            &dir,
+            WatchMask::CLOSE_WRITE
+                | WatchMask::MOVED_TO
+                | WatchMask::MOVED_FROM
+                | WatchMask::DELETE,
+        )?;
+
+        let plugins = self.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0; 4096];
+            while let Ok(events) = inotify.read_events_blocking(&mut buffer) {
+                let mut changed: HashSet<PathBuf> = plugin_paths(&dir, events).collect();
+                std::thread::sleep(SETTLE_TIME);
+                while let Ok(events) = inotify.read_events(&mut buffer) {
+                    changed.extend(plugin_paths(&dir, events));
+                }
+
+                let mut changed: Vec<PathBuf> = changed.into_iter().collect();
+                changed.sort();
+                plugins.hot_reload(&changed);
+                on_changed();
+            }
+        });
+
+        Ok(())
+    }
+
+    fn hot_reload(&self, paths: &[PathBuf]) {
+        let engine = self.manager.lock().unwrap().engine.clone();
+        // Compiling takes a while, queries keep running meanwhile
+        let compiled: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                let id = plugin_id(path);
+                if !path.exists() {
+                    // The built-in it replaced takes over again
+                    let builtin = builtin_source(&id)
+                        .map(|(wasm, manifest)| compile(&engine, path, wasm, manifest, true));
+                    return (id, builtin);
+                }
+                let compiled = std::fs::read(path)
+                    .map_err(|e| Box::<dyn Error>::from(format!("Failed to read {}: {}", id, e)))
+                    .and_then(|bytes| {
+                        let manifest = std::fs::read_to_string(path.with_extension("toml")).ok();
+                        compile(&engine, path, &bytes, manifest.as_deref(), false)
+                    });
+                (id, Some(compiled))
+            })
+            .collect();
+
+        let mut manager = self.manager.lock().unwrap();
+        // A full reload in the meantime already picked up the same files
+        if !Engine::same(&manager.engine, &engine) {
+            return;
+        }
+        self.run_activations(&mut manager);
+
+        let mut settings = config::load_plugin_settings();
+        for (id, compiled) in compiled {
+            manager.unregister(&id);
+            match compiled {
+                None => println!("Removed plugin {}", id),
+                Some(Ok(compiled)) => {
+                    if compiled.builtin {
+                        println!("Restored built-in plugin {}", id);
+                    } else {
+                        println!("Reloaded plugin {}", id);
+                    }
+                    manager.add(compiled, settings.remove(&id).unwrap_or_default());
+                }
+                Some(Err(e)) => manager.report(&id, e.to_string()),
+            }
+        }
+        self.publish(&manager);
+    }
+
+    /// Makes the triggers and whether there are global plugins readable without the lock.
+    fn publish(&self, manager: &PluginManager) {
+        *self.triggers.write().unwrap() = manager
+            .plugins
+            .iter()
+            .filter_map(|plugin| plugin.prefix.clone())
+            .collect();
+        self.has_global.store(
+            manager.plugins.iter().any(|plugin| plugin.global),
+            Ordering::SeqCst,
+        );
+    }
+
+    /// Tells plugin `provider` the user picked its item `item_id`. Queued, so a
+    /// `shutdown` right after still delivers it.
+    pub fn activate(&self, provider: String, item_id: String) {
+        self.activations.lock().unwrap().push((provider, item_id));
+        let plugins = self.clone();
+        rayon::spawn(move || {
+            let mut manager = plugins.manager.lock().unwrap();
+            plugins.run_activations(&mut manager);
+        });
+    }
+
+    fn run_activations(&self, manager: &mut PluginManager) {
+        let pending = std::mem::take(&mut *self.activations.lock().unwrap());
+        for (provider, item_id) in pending {
+            manager.activate(&provider, &item_id);
+        }
+    }
+
+    /// Lets every running plugin flush its state before the launcher exits, blocks until done.
+    pub fn shutdown(&self) {
+        let mut manager = self.manager.lock().unwrap();
+        self.run_activations(&mut manager);
+        manager.shutdown();
+    }
+
+    /// Drops the results of every query still running.
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Skipped when a query holds the manager, the next empty query shows them.
+    pub fn notices(&self) -> Vec<ActionItem> {
+        match self.manager.try_lock() {
+            Ok(manager) => manager.notices(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn approve(&self, id: &str) -> Result<(), Box<dyn Error>> {
+        self.manager.lock().unwrap().approve(id)
+    }
+}
+
+struct Plugin {
+    // File name without the extension
+    id: String,
+    // What the plugin itself asked for, it gets queries starting with this
+    trigger: String,
+    // The trigger in effect, which the user may have overridden. None for purely global plugins
+    prefix: Option<String>,
+    // Also gets every query without a trigger
+    global: bool,
+    // Shipped inside the launcher, a user plugin file going away doesn't remove it
+    builtin: bool,
+    path: PathBuf,
+    // Entry in the compiled component cache
+    key: String,
+    // Hash of the component, approvals are tied to it
+    digest: String,
+    component: Component,
+    legacy: bool,
+    permissions: Permissions,
+    // Until the user approves the manifest, typing the trigger only shows what it asks for
+    approved: bool,
+    // Where the plugin's network hosts resolved to when it was loaded
+    addrs: Arc<Vec<IpAddr>>,
+    settings: PluginSettings,
+    // The manager's, its instances run against the same epoch
+    ticker: Ticker,
+    // Started on the first query and kept so the plugin can cache between keystrokes
+    instance: Option<Instance>,
+}
+
+struct Instance {
+    store: Store<MyState>,
+    world: World,
+    // Epoch ticks every call may take
+    deadline: u64,
+    ticker: Ticker,
+    // Started with write access to its data directory
+    writable: bool,
+}
+
+enum World {
+    Current(PluginWorld),
+    Legacy(legacy::PluginWorld),
+}
+
+impl Plugin {
+    /// Runs one query through the kept instance, starting a fresh one first if needed.
+    /// With a `sink` the plugin streams into it, the reply then holds whatever it didn't push.
+    fn query(
+        &mut self,
+        engine: &Engine,
+        linker: &Linker<MyState>,
+        input: &str,
+        mut sink: Option<ResultSink>,
+    ) -> Reply {
+        if !self.approved {
+            return Reply::Approval {
+                id: self.id.clone(),
+                permissions: self.permissions.describe(),
+            };
+        }
+
+        let mut instance = match self.instance.take() {
+            Some(instance) => instance,
+            None => match self.instantiate(engine, linker) {
+                Ok(instance) => instance,
+                Err(e) => return stopped(self, e),
+            },
+        };
+
+        // An instance that trapped can't be called again, so it's only put back after a clean call
+        if let Some(sink) = &mut sink {
+            sink.provider = self.id.clone();
+        }
+        let mut items = match instance.handle(input, sink) {
+            Ok(items) => {
+                self.keep(instance);
+                items
+            }
+            // Thrown away, the next query starts a fresh instance
+            Err(e) => return stopped(self, e),
+        };
+
+        items = items
+            .into_iter()
+            .map(|item| restrict(item, &self.permissions))
+            .collect();
+        // Stable, so unscored items keep the plugin's order
+        items.sort_by(|a, b| {
+            let score = |item: &runner::ActionItem| item.score.unwrap_or(f32::NEG_INFINITY);
+            score(b).total_cmp(&score(a))
+        });
+
+        Reply::Items {
+            id: self.id.clone(),
+            items,
+        }
+    }
+
+    fn activate(&mut self, engine: &Engine, linker: &Linker<MyState>, item_id: &str) {
+        if !self.approved {
+            return;
+        }
+        let instance = match self.instance.take() {
+            Some(instance) => Ok(instance),
+            None => self.instantiate(engine, linker),
+        };
+        match instance.and_then(|mut instance| instance.on_activate(item_id).map(|_| instance)) {
+            Ok(instance) => self.keep(instance),
+            Err(e) => eprintln!(
+                "Plugin {} failed to handle activation: {}",
+                self.id,
+                e.root_cause()
+            ),
+        }
+    }
+
+    /// What to show for item `item_id`. Image paths come back as host paths, and only
+    /// for images the plugin may read itself.
+    fn preview(
+        &mut self,
+        engine: &Engine,
+        linker: &Linker<MyState>,
+        item_id: &str,
+    ) -> PreviewContent {
+        if !self.approved {
+            return PreviewContent::None;
+        }
+        let instance = match self.instance.take() {
+            Some(instance) => Ok(instance),
+            None => self.instantiate(engine, linker),
+        };
+        let content = match instance.and_then(|mut instance| {
+            let content = instance.preview(item_id)?;
+            Ok((instance, content))
+        }) {
+            Ok((instance, content)) => {
+                self.keep(instance);
+                content
+            }
+            Err(e) => {
+                eprintln!(
+                    "Plugin {} failed to preview {}: {}",
+                    self.id,
+                    item_id,
+                    e.root_cause()
+                );
+                return PreviewContent::None;
+            }
+        };
+
+        match content {
+            PreviewContent::Image(path) => match self.host_path(&path) {
+                Some(host) => PreviewContent::Image(host.to_string_lossy().into_owned()),
+                None => {
+                    eprintln!(
+                        "Plugin {} can't preview {}, it may not read it",
+                        self.id, path
+                    );
+                    PreviewContent::None
+                }
+            },
+            PreviewContent::Text(text) if text.len() > PREVIEW_TEXT_LIMIT => {
+                PreviewContent::Text(text.chars().take(PREVIEW_TEXT_LIMIT).collect())
+            }
+            content => content,
+        }
+    }
+
+    /// Where `guest`, a path as the plugin sees it, is on the host. None outside its mounts,
+    /// also when a symlink or `..` leads out of them.
+    fn host_path(&self, guest: &str) -> Option<PathBuf> {
+        let data = (
+            storage::data_dir(&self.id).to_string_lossy().into_owned(),
+            storage::GUEST_PATH.to_string(),
+        );
+        self.permissions
+            .mounts()
+            .into_iter()
+            .chain([data])
+            .find_map(|(host, mount)| {
+                let rest = Path::new(guest).strip_prefix(&mount).ok()?;
+                let root = Path::new(&host).canonicalize().ok()?;
+                let path = root.join(rest).canonicalize().ok()?;
+                path.starts_with(&root).then_some(path)
+            })
+    }
+
+    /// Puts the instance back for the next call, unless that call took its data over the quota.
+    /// The next instance then gets the directory without write access.
+    fn keep(&mut self, instance: Instance) {
+        if instance.writable
+            && storage::usage(&storage::data_dir(&self.id)) >= self.settings.storage
+        {
+            eprintln!(
+                "Plugin {} is over its storage quota, its data is read-only now",
+                self.id
+            );
+            return;
+        }
+        self.instance = Some(instance);
+    }
+
+    fn instantiate(&self, engine: &Engine, linker: &Linker<MyState>) -> wasmtime::Result<Instance> {
+        // Start functions run too, they're under the same deadline
+        let _ticking = self.ticker.start();
+        let data = storage::data_dir(&self.id);
+        let writable = storage::usage(&data) < self.settings.storage;
+        let mut store = create_store(
+            engine,
+            &self.permissions,
+            Arc::clone(&self.addrs),
+            &self.settings,
+            Some((&data, writable)),
+        );
+        let world = if self.legacy {
+            World::Legacy(legacy::PluginWorld::instantiate(
+                &mut store,
+                &self.component,
+                linker,
+            )?)
+        } else {
+            World::Current(PluginWorld::instantiate(
+                &mut store,
+                &self.component,
+                linker,
+            )?)
+        };
+        let mut instance = Instance {
+            store,
+            world,
+            deadline: epoch_ticks(self.settings.timeout),
+            ticker: self.ticker.clone(),
+            writable,
+        };
+        instance.init(&self.settings.config)?;
+        Ok(instance)
+    }
+}
+
+impl Instance {
+    /// Gives the call its whole timeout and keeps the epoch going until the guard is dropped.
+    fn arm(&mut self) -> Ticking {
+        self.store.set_epoch_deadline(self.deadline);
+        self.ticker.start()
+    }
+
+    fn init(&mut self, config: &str) -> wasmtime::Result<()> {
+        let _ticking = self.arm();
+        match &self.world {
+            World::Current(world) => world
+                .swift_launcher_runner()
+                .call_init(&mut self.store, config)?
+                .map_err(wasmtime::Error::msg),
+            World::Legacy(_) => Ok(()),
+        }
+    }
+    /// Streams into `sink` when there is one and the plugin can, legacy plugins return
+    /// everything at once.
+    fn handle(
+        &mut self,
+        input: &str,
+        sink: Option<ResultSink>,
+    ) -> wasmtime::Result<Vec<runner::ActionItem>> {
+        let _ticking = self.arm();
+        match (&self.world, sink) {
+            (World::Current(world), Some(sink)) => {
+                let state = self.store.data_mut();
+                state.pushed = false;
+                let sink = state.table.push(sink)?;
+                let streamed = world.swift_launcher_runner().call_handle_streaming(
+                    &mut self.store,
+                    input,
+                    Resource::new_borrow(sink.rep()),
+                );
+                // Pushes don't extend whatever gets called next
+                let state = self.store.data_mut();
+                state.pushed = false;
+                state.table.delete(sink)?;
+                streamed.map(|_| Vec::new())
+            }
+            (World::Current(world), None) => world
+                .swift_launcher_runner()
+                .call_handle(&mut self.store, input),
+            (World::Legacy(world), _) => Ok(world
+                .swift_launcher_runner()
+                .call_handle(&mut self.store, input)?
+                .into_iter()
+                .map(from_legacy)
+                .collect()),
+        }
+    }
+
+    fn on_activate(&mut self, item_id: &str) -> wasmtime::Result<()> {
+        let _ticking = self.arm();
+        match &self.world {
+            World::Current(world) => world
+                .swift_launcher_runner()
+                .call_on_activate(&mut self.store, item_id),
+            World::Legacy(_) => Ok(()),
+        }
+    }
+
+    fn preview(&mut self, item_id: &str) -> wasmtime::Result<PreviewContent> {
+        let _ticking = self.arm();
+        match &self.world {
+            World::Current(world) => world
+                .swift_launcher_runner()
+                .call_preview(&mut self.store, item_id),
+            World::Legacy(_) => Ok(PreviewContent::None),
+        }
+    }
+
+    fn shutdown(&mut self) -> wasmtime::Result<()> {
+        let _ticking = self.arm();
+        match &self.world {
+            World::Current(world) => world.swift_launcher_runner().call_shutdown(&mut self.store),
+            World::Legacy(_) => Ok(()),
+        }
+    }
 }
 
 impl WasiView for MyState {
@@ -41,131 +811,975 @@ impl WasiHttpView for MyState {
     fn table(&mut self) -> &mut ResourceTable {
         &mut self.table
     }
+
+    fn send_request(
+        &mut self,
+        request: hyper::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        let host = request.uri().host().unwrap_or("");
+        if !self.permissions.allows_interface("http") || !self.permissions.allows_host(host) {
+            return Err(ErrorCode::HttpRequestDenied.into());
+        }
+        Ok(default_send_request(request, config))
+    }
+}
+
+impl swift::launcher::types::Host for MyState {}
+impl swift::launcher::results::Host for MyState {}
+
+impl swift::launcher::results::HostResultSink for MyState {
+    fn push(&mut self, sink: Resource<ResultSink>, items: Vec<runner::ActionItem>) -> bool {
+        let Ok(sink) = self.table.get(&sink) else {
+            return false;
+        };
+        if !(sink.is_current)() {
+            return false;
+        }
+        (sink.on_batch)(Reply::Items {
+            id: sink.provider.clone(),
+            items: items
+                .into_iter()
+                .map(|item| restrict(item, &self.permissions))
+                .collect(),
+        });
+        self.pushed = true;
+        true
+    }
+
+    fn drop(&mut self, sink: Resource<ResultSink>) -> wasmtime::Result<()> {
+        self.table.delete(sink)?;
+        Ok(())
+    }
+}
+
+impl swift::launcher::clipboard::Host for MyState {
+    fn set_text(&mut self, text: String) -> Result<(), String> {
+        if !self.permissions.allows_interface("clipboard") {
+            return Err("Plugin has no clipboard permission".to_string());
+        }
+        clipboard::copy_text(&text).map_err(|e| e.to_string())
+    }
 }
 
 impl PluginManager {
     pub fn new() -> Self {
         let mut config = Config::new();
         config.wasm_component_model(true);
+        config.epoch_interruption(true);
         // config.async_support(true);
         let engine = Engine::new(&config).expect("WASM engine failed");
 
+        let ticker = Ticker::spawn(&engine);
+
         let mut linker = Linker::new(&engine);
         wasmtime_wasi::p2::add_to_linker_sync(&mut linker).expect("Failed to add WASI");
         wasmtime_wasi_http::add_only_http_to_linker_sync(&mut linker)
             .expect("Failed to add WASI_HTTP");
+        swift::launcher::clipboard::add_to_linker::<MyState, HasSelf<MyState>>(
+            &mut linker,
+            |state| state,
+        )
+        .expect("Failed to add clipboard");
+        swift::launcher::types::add_to_linker::<MyState, HasSelf<MyState>>(&mut linker, |state| {
+            state
+        })
+        .expect("Failed to add plugin types");
+        swift::launcher::results::add_to_linker::<MyState, HasSelf<MyState>>(
+            &mut linker,
+            |state| state,
+        )
+        .expect("Failed to add result sink");
         Self {
             engine: engine.clone(),
             linker,
-            plugins: HashMap::new(),
+            ticker,
+            plugins: Vec::new(),
+            load_errors: Vec::new(),
+            disabled: Vec::new(),
         }
     }
 
-    pub fn load_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn load_all(&mut self) -> Result<(), Box<dyn Error>> {
         let mut plugin_sources = Vec::new();
 
         // 2. Load User Plugins (from disk)
-        let user_path = format!(
-            "{}/swift/plugins",
-            std::env::var("XDG_CONFIG_HOME")
-                .unwrap_or_else(|_| format!("{}/.config", std::env::var("HOME").unwrap()))
-        );
+        let user_path = user_dir();
 
-        for file in BUILTIN_PLUGINS.files() {
+        for id in builtin_ids() {
             // println!("Loading: {:?}", file);
             // self.register(file.contents())?;
 
-            // println!("{:?}", file.path());
-            let path = PathBuf::from(format!("{}/{}", user_path, file.path().display()));
-            plugin_sources.push((path.to_path_buf(), file.contents().to_vec()))
+            let Some((wasm, manifest)) = builtin_source(&id) else {
+                continue;
+            };
+            let path = user_path.join(format!("{}.wasm", id));
+            plugin_sources.push((path, wasm.to_vec(), manifest.map(str::to_string), true))
         }
 
         if let Ok(entries) = std::fs::read_dir(&user_path) {
-            for entry in entries.flatten() {
+            // Sorted, so which of two conflicting plugins wins doesn't change between loads
+            let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            paths.sort();
+
+            for path in paths {
+                // Older versions cached compiled plugins right next to them
+                if path.extension().and_then(|s| s.to_str()) == Some("cwasm") {
+                    let _ = std::fs::remove_file(&path);
+                    continue;
+                }
                 // println!("Loading {:?}", entry);
-                let path = entry.path();
                 if path.extension().and_then(|s| s.to_str()) == Some("wasm") {
                     // println!("Loaded");
                     // self.register(&std::fs::read(entry.path())?)?;
-                    plugin_sources.push((path, std::fs::read(entry.path())?))
+                    let manifest = std::fs::read_to_string(path.with_extension("toml")).ok();
+                    let bytes = std::fs::read(&path)?;
+                    plugin_sources.push((path, bytes, manifest, false))
                 }
             }
         }
 
+        // A user plugin replaces the built-in of the same id, even if it then fails to load
+        let user_ids: HashSet<String> = plugin_sources
+            .iter()
+            .filter(|(_, _, _, builtin)| !builtin)
+            .map(|(path, _, _, _)| plugin_id(path))
+            .collect();
+        plugin_sources
+            .retain(|(path, _, _, builtin)| !builtin || !user_ids.contains(&plugin_id(path)));
+
         let engine = self.engine.clone();
+        let mut settings = config::load_plugin_settings();
+        // Not even compiled, a broken plugin can be turned off this way
+        plugin_sources.retain(|(path, _, _, builtin)| {
+            let id = plugin_id(path);
+            if settings.get(&id).is_none_or(|settings| settings.enabled) {
+                return true;
+            }
+            self.disabled.push(Disabled {
+                id,
+                path: path.clone(),
+                builtin: *builtin,
+            });
+            false
+        });
         let compiled_results: Vec<_> = plugin_sources
             .into_iter()
-            .map(|(path, bytes)| {
-                println!("Processing {:?} at {:?}", path, current_thread_index());
-                let cwasm_path = path.with_extension("cwasm");
-
-                if cwasm_path.exists() {
-                    println!("Using cwasm");
-                    unsafe { Component::deserialize_file(&engine, &cwasm_path) }
-                } else {
-                    println!("Compiling module");
-                    let component = Component::from_binary(&engine, &bytes)?;
-
-                    let _ = std::fs::write(cwasm_path, engine.precompile_component(&bytes)?);
-                    Ok(component)
-                }
+            .map(|(path, bytes, manifest, builtin)| {
+                let compiled = compile(&engine, &path, &bytes, manifest.as_deref(), builtin);
+                (plugin_id(&path), compiled)
             })
             .collect();
 
-        for result in compiled_results {
-            self.register(result?)?;
+        // Entries of plugins that changed or went away are never hit again
+        cache::retain(
+            &compiled_results
+                .iter()
+                .filter_map(|(_, result)| Some(result.as_ref().ok()?.key.clone()))
+                .collect(),
+        );
+
+        // A plugin that is broken, hangs while loading or clashes with another one shouldn't
+        // take the others with it
+        for (id, result) in compiled_results {
+            match result {
+                Ok(compiled) => {
+                    let settings = settings.remove(&compiled.id).unwrap_or_default();
+                    self.add(compiled, settings);
+                }
+                Err(e) => self.report(&id, e.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers a compiled plugin, reporting why if it can't be.
+    fn add(&mut self, compiled: Compiled, settings: PluginSettings) {
+        let id = compiled.id.clone();
+        if !settings.enabled {
+            self.disabled.push(Disabled {
+                id,
+                path: compiled.path,
+                builtin: compiled.builtin,
+            });
+            return;
         }
+        if let Err(e) = self.register(compiled, settings) {
+            self.report(&id, format!("Failed to load plugin {}: {}", id, e));
+        }
+    }
+
+    /// Drops plugin `id`, the user copy or the built-in it replaced, and anything reported
+    /// about it. Its instance gets to shut down first.
+    fn unregister(&mut self, id: &str) {
+        self.load_errors.retain(|(errored, _)| errored != id);
+        self.disabled.retain(|disabled| disabled.id != id);
+        let Some(index) = self.plugins.iter().position(|plugin| plugin.id == id) else {
+            return;
+        };
+
+        let mut plugin = self.plugins.remove(index);
+        if let Some(mut instance) = plugin.instance.take()
+            && let Err(e) = instance.shutdown()
+        {
+            eprintln!("Plugin {} failed to shut down: {}", id, e.root_cause());
+        }
+    }
 
+    fn register(
+        &mut self,
+        compiled: Compiled,
+        settings: PluginSettings,
+    ) -> Result<(), Box<dyn Error>> {
+        let Compiled {
+            id,
+            path,
+            component,
+            permissions,
+            builtin,
+            key,
+            digest,
+        } = compiled;
+        let _ticking = self.ticker.start();
+        // Asking for the trigger doesn't need any of the permissions yet
+        let sandbox = Permissions::default();
+        let mut store = create_store(&self.engine, &sandbox, Arc::default(), &settings, None);
+        let (trigger, global, legacy) =
+            match PluginWorld::instantiate(&mut store, &component, &self.linker) {
+                Ok(world) => {
+                    let runner = world.swift_launcher_runner();
+                    let trigger = runner.call_get_trigger(&mut store)?;
+                    (trigger, runner.call_is_global(&mut store)?, false)
+                }
+                // Only a component without the current runner is a legacy one, anything else
+                // failing is a broken plugin and the legacy world would only hide why
+                Err(e) if component.get_export_index(None, RUNNER_INTERFACE).is_some() => {
+                    return Err(e.into());
+                }
+                Err(_) => {
+                    let mut store =
+                        create_store(&self.engine, &sandbox, Arc::default(), &settings, None);
+                    let world =
+                        legacy::PluginWorld::instantiate(&mut store, &component, &self.linker)?;
+                    (
+                        world.swift_launcher_runner().call_get_trigger(&mut store)?,
+                        false,
+                        true,
+                    )
+                }
+            };
+
+        let prefix = Some(settings.trigger.clone().unwrap_or_else(|| trigger.clone()))
+            .filter(|prefix| !prefix.is_empty());
+        if prefix.is_none() && !global {
+            return Err("it has no trigger".into());
+        }
+        if let Some(active) = &prefix
+            && let Some(existing) = self
+                .plugins
+                .iter()
+                .find(|plugin| plugin.prefix.as_ref() == Some(active))
+        {
+            return Err(format!(
+                "trigger {:?} is already used by {}, set another one in [plugin:{}]",
+                active, existing.id, id
+            )
+            .into());
+        }
+        if legacy {
+            eprintln!("Plugin {} uses the legacy interface", id);
+        }
+
+        // Built-ins ship with the launcher, asking for nothing needs no approval
+        let approved = builtin
+            || permissions.is_empty()
+            || permissions::load_approved(&id, &digest)
+                .is_some_and(|granted| granted.covers(&permissions));
+        let addrs = Arc::new(permissions.resolve_hosts());
+
+        self.plugins.push(Plugin {
+            id,
+            trigger,
+            prefix,
+            global,
+            component,
+            legacy,
+            permissions,
+            approved,
+            addrs,
+            settings,
+            ticker: self.ticker.clone(),
+            builtin,
+            path,
+            key,
+            digest,
+            instance: None,
+        });
         Ok(())
     }
 
-    fn register(&mut self, component: Component) -> Result<(), Box<dyn std::error::Error>> {
-        let mut store = self.create_store();
-        let world = PluginWorld::instantiate(&mut store, &component, &self.linker)?;
-        if let Ok(t) = world.swift_launcher_runner().call_get_trigger(&mut store)
-            && let Some(c) = t.chars().next() {
-                self.plugins.insert(c, component);
+    fn report(&mut self, id: &str, error: String) {
+        eprintln!("{}", error);
+        self.load_errors.push((id.to_string(), error));
+    }
+
+    /// Rows for everything waiting on the user: plugins to approve and plugins that failed to load.
+    pub fn notices(&self) -> Vec<ActionItem> {
+        let approvals = self
+            .plugins
+            .iter()
+            .filter(|plugin| !plugin.approved)
+            .map(|plugin| approval_item(&plugin.id, &plugin.permissions.describe()));
+
+        let errors = self.load_errors.iter().map(|(_, error)| ActionItem {
+            name: error.as_str().into(),
+            icon_name: "dialog-warning".into(),
+            kind: ActionKind::None,
+            ..Default::default()
+        });
+
+        approvals.chain(errors).collect()
+    }
+
+    /// Everything known about every plugin, loaded, turned off or broken, sorted by id.
+    pub fn infos(&self) -> Vec<PluginInfo> {
+        let loaded = self.plugins.iter().map(|plugin| PluginInfo {
+            id: plugin.id.clone(),
+            path: plugin.path.clone(),
+            builtin: plugin.builtin,
+            enabled: true,
+            trigger: plugin.prefix.clone(),
+            native_trigger: Some(plugin.trigger.clone())
+                .filter(|native| !native.is_empty() && plugin.prefix.as_ref() != Some(native)),
+            global: plugin.global,
+            legacy: plugin.legacy,
+            cached: cache::is_cached(&plugin.key),
+            permissions: Some(plugin.permissions.clone()),
+            approved: plugin.approved,
+            error: None,
+            overridden: false,
+        });
+        let disabled = self.disabled.iter().map(|disabled| PluginInfo {
+            id: disabled.id.clone(),
+            path: disabled.path.clone(),
+            builtin: disabled.builtin,
+            ..Default::default()
+        });
+        let failed = self.load_errors.iter().map(|(id, error)| {
+            let path = user_dir().join(format!("{}.wasm", id));
+            PluginInfo {
+                id: id.clone(),
+                builtin: !path.exists() && builtin_source(id).is_some(),
+                path,
+                enabled: true,
+                error: Some(error.clone()),
+                ..Default::default()
             }
+        });
+
+        let mut infos: Vec<PluginInfo> = loaded.chain(disabled).chain(failed).collect();
+        let overridden: Vec<PluginInfo> = builtin_ids()
+            .filter(|id| {
+                let mut copies = infos.iter().filter(|info| &info.id == id);
+                copies.clone().any(|info| !info.builtin) && !copies.any(|info| info.builtin)
+            })
+            .map(|id| PluginInfo {
+                path: user_dir().join(format!("{}.wasm", id)),
+                id,
+                builtin: true,
+                overridden: true,
+                ..Default::default()
+            })
+            .collect();
+        infos.extend(overridden);
+        infos.sort_by(|a, b| a.id.cmp(&b.id).then(a.builtin.cmp(&b.builtin)));
+        infos
+    }
+
+    /// Loads `file` as user plugin in place of any installed copy, reporting why it wouldn't
+    /// load next to the others: not a plugin, no trigger or a trigger already taken.
+    pub fn check(&mut self, file: &Path) -> Result<PluginInfo, Box<dyn Error>> {
+        let id = plugin_id(file);
+        let bytes = std::fs::read(file)?;
+        let manifest = std::fs::read_to_string(file.with_extension("toml")).ok();
+        let mut compiled = compile(&self.engine, file, &bytes, manifest.as_deref(), false)?;
+        compiled.path = user_dir().join(format!("{}.wasm", id));
+
+        self.unregister(&id);
+        let settings = config::load_plugin_settings()
+            .remove(&id)
+            .unwrap_or_default();
+        self.register(compiled, settings)?;
+
+        Ok(self
+            .infos()
+            .into_iter()
+            .find(|info| info.id == id && !info.builtin)
+            .unwrap_or_default())
+    }
+
+    /// Grants plugin `id` everything its manifest asks for, and remembers it.
+    pub fn approve(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
+        let plugin = self
+            .plugins
+            .iter_mut()
+            .find(|plugin| plugin.id == id)
+            .ok_or_else(|| format!("No plugin called {}", id))?;
+
+        permissions::approve(id, &plugin.digest, &plugin.permissions)?;
+        plugin.approved = true;
         Ok(())
     }
 
+    /// Runs `input`, which starts with `trigger`, through the plugin registered for it.
     pub fn run_trigger(
-        &self,
-        trigger: char,
+        &mut self,
+        trigger: &str,
         input: &str,
-    ) -> Option<Vec<exports::swift::launcher::runner::ActionItem>> {
-        let comp = self.plugins.get(&trigger)?;
-        let mut store = self.create_store();
-        let world = PluginWorld::instantiate(&mut store, comp, &self.linker).ok()?;
-        world
-            .swift_launcher_runner()
-            .call_handle(&mut store, input)
-            .ok()
-    }
-
-    fn create_store(&self) -> Store<MyState> {
-        let config_dir = format!("{}/.config/swift", env::var("HOME").expect("Could not find home directory"));
-        println!("{}", config_dir);
-        
-        let mut builder = WasiCtxBuilder::new();
-        builder.inherit_stdio();
-
-        builder.inherit_network();
-
-        builder
-            .preopened_dir("/", "/", DirPerms::READ, FilePerms::READ)
-            .expect("Failed to preopen /");
-
-        builder.preopened_dir(config_dir, "/config", DirPerms::READ, FilePerms::READ)
-               .expect("Failed to preopen /config");
-
-        Store::new(
-            &self.engine,
-            MyState {
-                wasi: builder.build(),
-                http_ctx: WasiHttpCtx::new(),
-                table: ResourceTable::new(),
-            },
-        )
+        sink: Option<ResultSink>,
+    ) -> Option<Reply> {
+        let plugin = self
+            .plugins
+            .iter_mut()
+            .find(|plugin| plugin.prefix.as_deref() == Some(trigger))?;
+
+        // Plugins strip their own trigger, not the one the user picked for them
+        let input = format!(
+            "{}{}",
+            plugin.trigger,
+            input.strip_prefix(trigger).unwrap_or(input)
+        );
+        Some(plugin.query(&self.engine, &self.linker, &input, sink))
+    }
+
+    /// Passes a picked item back to the plugin that returned it.
+    pub fn activate(&mut self, provider: &str, item_id: &str) {
+        if let Some(plugin) = self.plugins.iter_mut().find(|plugin| plugin.id == provider) {
+            plugin.activate(&self.engine, &self.linker, item_id);
+        }
+    }
+
+    /// What plugin `provider` shows for its item `item_id`, nothing if it's gone since.
+    pub fn preview(&mut self, provider: &str, item_id: &str) -> PreviewContent {
+        match self.plugins.iter_mut().find(|plugin| plugin.id == provider) {
+            Some(plugin) => plugin.preview(&self.engine, &self.linker, item_id),
+            None => PreviewContent::None,
+        }
+    }
+
+    /// Calls `shutdown` on every running instance and drops them.
+    pub fn shutdown(&mut self) {
+        for plugin in &mut self.plugins {
+            if let Some(mut instance) = plugin.instance.take()
+                && let Err(e) = instance.shutdown()
+            {
+                eprintln!(
+                    "Plugin {} failed to shut down: {}",
+                    plugin.id,
+                    e.root_cause()
+                );
+            }
+        }
+    }
+
+    /// Runs a query without a trigger through every approved global plugin at once,
+    /// keeping at most `max-results` items of each.
+    ///
+    /// Each plugin gets its own scoped thread rather than a rayon task: this runs inside
+    /// a rayon job holding the manager lock, and a worker waiting on the pool picks up
+    /// other queued jobs, which may be queries blocking on that same lock.
+    pub fn run_global(&mut self, input: &str) -> Vec<Reply> {
+        let (engine, linker) = (&self.engine, &self.linker);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .plugins
+                .iter_mut()
+                .filter(|plugin| plugin.global && plugin.approved)
+                .map(|plugin| {
+                    scope.spawn(move || {
+                        let mut reply = plugin.query(engine, linker, input, None);
+                        if let Reply::Items { items, .. } = &mut reply {
+                            items.truncate(plugin.settings.max_results);
+                        }
+                        reply
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().ok())
+                .collect()
+        })
+    }
+}
+
+impl Drop for PluginManager {
+    // Lets the ticker thread exit, it's parked unless a call is running
+    fn drop(&mut self) {
+        self.ticker.stop();
+    }
+}
+
+/// `$XDG_CONFIG_HOME/swift/plugins`, where users drop their own plugins.
+pub fn user_dir() -> PathBuf {
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .unwrap_or_else(|_| format!("{}/.config", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(format!("{}/swift/plugins", config_home))
+}
+
+/// Ids of the plugins shipped with the launcher.
+fn builtin_ids() -> impl Iterator<Item = String> {
+    BUILTIN_PLUGINS
+        .files()
+        .filter(|file| file.path().extension().and_then(|s| s.to_str()) == Some("wasm"))
+        .map(|file| plugin_id(file.path()))
+}
+
+/// The component and manifest of built-in plugin `id`.
+pub fn builtin_source(id: &str) -> Option<(&'static [u8], Option<&'static str>)> {
+    let wasm = BUILTIN_PLUGINS.get_file(format!("{}.wasm", id))?;
+    let manifest = BUILTIN_PLUGINS
+        .get_file(format!("{}.toml", id))
+        .and_then(|manifest| manifest.contents_utf8());
+    Some((wasm.contents(), manifest))
+}
+
+/// File name without the extension.
+fn plugin_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The `.wasm` each event is about, a changed manifest counts as a change of its plugin.
+fn plugin_paths<'a>(dir: &'a Path, events: Events<'a>) -> impl Iterator<Item = PathBuf> + 'a {
+    events
+        .filter_map(|event| event.name)
+        .map(|name| dir.join(name))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|s| s.to_str()),
+                Some("wasm" | "toml")
+            )
+        })
+        .map(|path| path.with_extension("wasm"))
+}
+
+/// Reads the permissions and compiles the component, or takes it from the cache.
+fn compile(
+    engine: &Engine,
+    path: &Path,
+    bytes: &[u8],
+    manifest: Option<&str>,
+    builtin: bool,
+) -> Result<Compiled, Box<dyn Error>> {
+    let id = plugin_id(path);
+    let permissions = Permissions::find(manifest, bytes)
+        .map_err(|e| format!("Invalid permission manifest for {}: {}", id, e))?;
+    let key = cache::key(engine, bytes);
+    let digest = permissions::digest(bytes);
+    let component =
+        cache::load(engine, &key, bytes).map_err(|e| format!("Failed to compile {}: {}", id, e))?;
+
+    Ok(Compiled {
+        id,
+        path: path.to_path_buf(),
+        component,
+        permissions,
+        builtin,
+        key,
+        digest,
+    })
+}
+
+/// A sandbox holding only what `permissions` grants, `addrs` are the resolved network hosts.
+/// `data` is the plugin's own directory and whether it's still under quota.
+/// Limits come from `settings`, and the deadline already covers instantiation.
+fn create_store(
+    engine: &Engine,
+    permissions: &Permissions,
+    addrs: Arc<Vec<IpAddr>>,
+    settings: &PluginSettings,
+    data: Option<(&Path, bool)>,
+) -> Store<MyState> {
+    let mut builder = WasiCtxBuilder::new();
+    builder.inherit_stdio();
+
+    // Name lookups stay off, a guest resolving any name it likes could leak data through the
+    // queries. Sockets only reach the addresses the declared hosts resolved to on the host
+    if !permissions.network.is_empty() {
+        builder.socket_addr_check(move |addr, _| {
+            let allowed = addrs.contains(&addr.ip());
+            Box::pin(async move { allowed })
+        });
+    }
+
+    for (host, guest) in permissions.mounts() {
+        // A missing directory shouldn't keep the plugin from running
+        if let Err(e) = builder.preopened_dir(&host, &guest, DirPerms::READ, FilePerms::READ) {
+            eprintln!("Failed to preopen {}: {}", host, e);
+        }
+    }
+
+    if let Some((dir, writable)) = data {
+        // Over quota the plugin can only read. The quota is soft, a call that's under it when
+        // it starts can write past it, `keep` catches that once the call returns
+        let (dir_perms, file_perms) = if writable {
+            (DirPerms::all(), FilePerms::all())
+        } else {
+            (DirPerms::READ, FilePerms::READ)
+        };
+        let mounted = std::fs::create_dir_all(dir)
+            .map_err(wasmtime::Error::from)
+            .and_then(|_| builder.preopened_dir(dir, storage::GUEST_PATH, dir_perms, file_perms));
+        if let Err(e) = mounted {
+            eprintln!("Failed to preopen {}: {}", dir.display(), e);
+        }
+    }
+
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(settings.memory)
+        .table_elements(settings.table_elements)
+        .trap_on_grow_failure(true)
+        .build();
+
+    let mut store = Store::new(
+        engine,
+        MyState {
+            wasi: builder.build(),
+            http_ctx: WasiHttpCtx::new(),
+            table: ResourceTable::new(),
+            permissions: permissions.clone(),
+            limits,
+            pushed: false,
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    store.set_epoch_deadline(epoch_ticks(settings.timeout));
+    // Out of time, unless a streaming query pushed results since the last deadline
+    let ticks = epoch_ticks(settings.timeout);
+    store.epoch_deadline_callback(move |mut store| {
+        if std::mem::take(&mut store.data_mut().pushed) {
+            Ok(UpdateDeadline::Continue(ticks))
+        } else {
+            Err(Trap::Interrupt.into())
+        }
+    });
+    store
+}
+
+/// Advances the engine's epoch every `EPOCH_TICK` while any plugin call is running, and parks
+/// in between so an idle launcher or daemon doesn't wake up for nothing.
+#[derive(Clone)]
+struct Ticker(Arc<TickerState>);
+
+#[derive(Default)]
+struct TickerState {
+    // Calls running right now, and whether the manager is gone
+    running: Mutex<(usize, bool)>,
+    wake: Condvar,
+}
+
+impl Ticker {
+    fn spawn(engine: &Engine) -> Self {
+        let state = Arc::new(TickerState::default());
+        let ticker = Arc::clone(&state);
+        let weak = engine.weak();
+        std::thread::spawn(move || {
+            loop {
+                let running = ticker.running.lock().unwrap();
+                let (_, stopped) = *ticker
+                    .wake
+                    .wait_while(running, |(calls, stopped)| *calls == 0 && !*stopped)
+                    .unwrap();
+                if stopped {
+                    return;
+                }
+
+                std::thread::sleep(EPOCH_TICK);
+                let Some(engine) = weak.upgrade() else {
+                    return;
+                };
+                engine.increment_epoch();
+            }
+        });
+        Self(state)
+    }
+
+    /// Keeps the epoch advancing until the returned guard is dropped.
+    fn start(&self) -> Ticking {
+        self.0.running.lock().unwrap().0 += 1;
+        self.0.wake.notify_one();
+        Ticking(self.clone())
+    }
+
+    fn stop(&self) {
+        self.0.running.lock().unwrap().1 = true;
+        self.0.wake.notify_one();
+    }
+}
+
+/// A running call, see `Ticker::start`.
+struct Ticking(Ticker);
+
+impl Drop for Ticking {
+    fn drop(&mut self) {
+        self.0.0.running.lock().unwrap().0 -= 1;
+    }
+}
+
+fn epoch_ticks(timeout: Duration) -> u64 {
+    (timeout.as_millis() / EPOCH_TICK.as_millis()).max(1) as u64
+}
+
+/// Turns a trap into a row saying which plugin was stopped and why.
+fn stopped(plugin: &Plugin, e: wasmtime::Error) -> Reply {
+    let reason = if e.downcast_ref::<Trap>() == Some(&Trap::Interrupt) {
+        format!("Took longer than {}ms", plugin.settings.timeout.as_millis())
+    } else {
+        e.root_cause().to_string()
+    };
+    eprintln!("Stopped plugin {}: {}", plugin.id, reason);
+
+    Reply::Failed {
+        id: plugin.id.clone(),
+        reason,
+    }
+}
+
+/// Selecting it and confirming with a second Enter grants the plugin what its manifest asks for.
+fn approval_item(id: &str, permissions: &str) -> ActionItem {
+    ActionItem {
+        name: format!("Allow plugin {}", id).into(),
+        exec: id.into(),
+        comment: permissions.into(),
+        icon_name: "dialog-password".into(),
+        kind: ActionKind::AllowPlugin,
+        ..Default::default()
+    }
+}
+
+/// Characters that only mean something to a shell: pipes, redirections, substitutions and globs.
+const SHELL_METACHARACTERS: &[char] = &['|', '&', ';', '<', '>', '(', ')', '$', '`', '*', '?', '~'];
+
+/// Legacy plugins only had a command line, which is split like an `Exec` key instead of
+/// going through a shell, unless it needs one for pipes, redirections or substitutions.
+fn from_legacy(item: legacy::exports::swift::launcher::runner::ActionItem) -> runner::ActionItem {
+    let codes = FieldCodes {
+        name: &item.name,
+        icon: "",
+        file: "",
+    };
+    let action = if item.exec.contains(SHELL_METACHARACTERS) {
+        Action::Shell(item.exec)
+    } else {
+        match parse_exec(&item.exec, &codes) {
+            Ok(argv) if !argv.is_empty() => Action::Spawn(argv),
+            _ => Action::None,
+        }
+    };
+
+    runner::ActionItem {
+        name: item.name,
+        action,
+        keywords: item.keywords,
+        description: None,
+        icon: None,
+        score: None,
+        id: None,
+    }
+}
+
+/// Actions that run something on the host need their interface in the manifest, an item with
+/// an undeclared one is still shown but does nothing.
+fn restrict(mut item: runner::ActionItem, permissions: &Permissions) -> runner::ActionItem {
+    let interface = match &item.action {
+        Action::Spawn(_) => "spawn",
+        Action::Shell(_) => "shell",
+        Action::OpenUri(_) => "open-uri",
+        Action::CopyText(_) | Action::None => return item,
+    };
+    if !permissions.allows_interface(interface) {
+        item.action = Action::None;
+    }
+    item
+}
+
+fn to_action_item(item: runner::ActionItem) -> ActionItem {
+    let (kind, exec) = match item.action {
+        Action::Spawn(argv) => (ActionKind::Exec, quote_argv(&argv)),
+        Action::OpenUri(uri) => (ActionKind::OpenUri, uri),
+        Action::CopyText(text) => (ActionKind::CopyText, text),
+        Action::Shell(command) => (ActionKind::Shell, command),
+        Action::None => (ActionKind::None, String::new()),
+    };
+
+    ActionItem {
+        name: item.name.into(),
+        exec: exec.into(),
+        keywords: item.keywords.into(),
+        comment: item.description.unwrap_or_default().into(),
+        icon_name: item.icon.unwrap_or_default().into(),
+        kind,
+        item_id: item.id.unwrap_or_default().into(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Once;
+    use std::time::{Duration, Instant};
+
+    // Typing a calculation, the plugin sees the whole query on every keystroke
+    const KEYSTROKES: &[&str] = &[
+        "=1", "=12", "=12*", "=12*3", "=12*34", "=12*34+", "=12*34+5",
+    ];
+    const ROUNDS: u32 = 20;
+
+    fn calc_manager() -> PluginManager {
+        // Instances get a data directory, it goes to scratch space instead of the developer's own
+        static SCRATCH_DATA: Once = Once::new();
+        SCRATCH_DATA.call_once(|| {
+            let dir = std::env::temp_dir().join("swift-launcher-tests");
+            // SAFETY: set before the first instance, and the environment is only read through std
+            unsafe { std::env::set_var("XDG_DATA_HOME", dir) };
+        });
+
+        let mut manager = PluginManager::new();
+        let wasm = BUILTIN_PLUGINS
+            .get_file("calc_plugin.wasm")
+            .expect("calc plugin is built in");
+        let component = Component::from_binary(&manager.engine, wasm.contents()).unwrap();
+        let compiled = Compiled {
+            id: "calc_plugin".to_string(),
+            path: PathBuf::from("calc_plugin.wasm"),
+            component,
+            permissions: Permissions::default(),
+            builtin: true,
+            key: String::new(),
+            digest: String::new(),
+        };
+        manager
+            .register(compiled, PluginSettings::default())
+            .unwrap();
+        manager
+    }
+
+    /// Average time per keystroke, `fresh` drops the instance before every call like queries used to.
+    fn per_keystroke(manager: &mut PluginManager, fresh: bool) -> Duration {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for input in KEYSTROKES {
+                if fresh {
+                    manager.plugins[0].instance = None;
+                }
+                assert!(manager.run_trigger("=", input, None).is_some());
+            }
+        }
+        start.elapsed() / (ROUNDS * KEYSTROKES.len() as u32)
+    }
+
+    #[test]
+    fn keeps_instance_between_queries() {
+        let mut manager = calc_manager();
+        assert!(manager.plugins[0].instance.is_none());
+
+        // Images never compare equal, so only what the plugin returned is compared
+        let rows = |manager: &mut PluginManager| -> Vec<(String, String)> {
+            manager
+                .run_trigger("=", "=1+2", None)
+                .unwrap()
+                .into_action_items()
+                .into_iter()
+                .map(|item| (item.name.to_string(), item.exec.to_string()))
+                .collect()
+        };
+        let first = rows(&mut manager);
+        assert!(manager.plugins[0].instance.is_some());
+
+        let second = rows(&mut manager);
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn drops_undeclared_host_actions() {
+        let item = |action| runner::ActionItem {
+            name: "item".into(),
+            action,
+            keywords: String::new(),
+            description: None,
+            icon: None,
+            score: None,
+            id: None,
+        };
+        let permissions = Permissions {
+            interfaces: vec!["open-uri".into()],
+            ..Default::default()
+        };
+
+        let spawned = restrict(item(Action::Spawn(vec!["sh".into()])), &permissions);
+        assert!(matches!(spawned.action, Action::None));
+        let shell = restrict(
+            item(Action::Shell("cat ~/.ssh/id_ed25519".into())),
+            &permissions,
+        );
+        assert!(matches!(shell.action, Action::None));
+        let opened = restrict(
+            item(Action::OpenUri("https://example.org".into())),
+            &permissions,
+        );
+        assert!(matches!(opened.action, Action::OpenUri(_)));
+        let copied = restrict(item(Action::CopyText("42".into())), &Permissions::default());
+        assert!(matches!(copied.action, Action::CopyText(_)));
+    }
+
+    #[test]
+    fn clamps_global_scores() {
+        let item = |score| runner::ActionItem {
+            name: "item".into(),
+            action: Action::None,
+            keywords: String::new(),
+            description: None,
+            icon: None,
+            score,
+            id: None,
+        };
+        let reply = Reply::Items {
+            id: "global".into(),
+            items: vec![
+                item(Some(1e9)),
+                item(Some(f32::INFINITY)),
+                item(Some(-3.0)),
+                item(Some(f32::NAN)),
+                item(Some(0.5)),
+            ],
+        };
+
+        let scores: Vec<i32> = reply
+            .into_action_items()
+            .iter()
+            .map(|item| item.score)
+            .collect();
+        assert_eq!(scores, vec![100, 100, 0, i32::MIN, 50]);
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `cargo test --release -- --ignored --nocapture`"]
+    fn keystroke_latency() {
+        let mut manager = calc_manager();
+        // Neither side should pay for the first call into freshly loaded code
+        per_keystroke(&mut manager, false);
+
+        let fresh = per_keystroke(&mut manager, true);
+        let reused = per_keystroke(&mut manager, false);
+        println!(
+            "Per keystroke: new instance {:?}, kept instance {:?}",
+            fresh, reused
+        );
+        assert!(reused < fresh);
     }
 }
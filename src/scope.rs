@@ -0,0 +1,159 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+/// Moves launched apps into their own transient `app-swift-<id>-<random>.scope`,
+/// so they outlive the launcher and get accounted for separately.
+pub struct ScopeLauncher {
+    connection: Connection,
+}
+
+impl ScopeLauncher {
+    /// Talks to the systemd user manager on the session bus.
+    pub fn session() -> zbus::Result<Self> {
+        Ok(Self::new(Connection::session()?))
+    }
+
+    /// Any connection that serves `org.freedesktop.systemd1`, e.g. a private mock bus.
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    pub fn move_to_scope(&self, app_id: &str, pid: u32) -> zbus::Result<String> {
+        let unit = unit_name(app_id);
+
+        let properties: Vec<(&str, Value)> = vec![
+            ("Description", Value::from(format!("Application launched by Swift launcher: {}", app_id))),
+            ("PIDs", Value::from(vec![pid])),
+            ("CollectMode", Value::from("inactive-or-failed")),
+        ];
+        let aux: Vec<(&str, Vec<(&str, Value)>)> = Vec::new();
+
+        self.connection.call_method(
+            Some("org.freedesktop.systemd1"),
+            "/org/freedesktop/systemd1",
+            Some("org.freedesktop.systemd1.Manager"),
+            "StartTransientUnit",
+            &(unit.as_str(), "fail", properties, aux),
+        )?;
+
+        Ok(unit)
+    }
+}
+
+/// `app-swift-<escaped id>-<random>.scope`, following the naming other desktop launchers use.
+pub fn unit_name(app_id: &str) -> String {
+    let app_id = app_id.strip_suffix(".desktop").unwrap_or(app_id);
+    let random = RandomState::new().hash_one(std::process::id()) as u32;
+    format!("app-swift-{}-{:08x}.scope", escape(app_id), random)
+}
+
+/// systemd unit name escaping, `-` is the separator so it has to go too.
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for (i, byte) in value.bytes().enumerate() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | b':' => escaped.push(byte as char),
+            b'.' if i > 0 => escaped.push('.'),
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::os::unix::net::UnixStream;
+    use std::sync::{Arc, Mutex};
+    use zbus::blocking::connection;
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+    // Units the stub was asked to start, with their properties
+    type Started = Arc<Mutex<Vec<(String, HashMap<String, OwnedValue>)>>>;
+
+    /// Stands in for the systemd user manager, only takes note of what it was asked.
+    struct Manager {
+        started: Started,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.systemd1.Manager")]
+    impl Manager {
+        fn start_transient_unit(
+            &self,
+            name: String,
+            _mode: String,
+            properties: Vec<(String, OwnedValue)>,
+            _aux: Vec<(String, Vec<(String, OwnedValue)>)>,
+        ) -> OwnedObjectPath {
+            self.started
+                .lock()
+                .unwrap()
+                .push((name, properties.into_iter().collect()));
+            OwnedObjectPath::try_from("/org/freedesktop/systemd1/job/1").unwrap()
+        }
+    }
+
+    /// A launcher talking to the stub over a private peer-to-peer connection. The server end is
+    /// returned too, the stub stops serving once it's dropped.
+    fn stub_launcher() -> (ScopeLauncher, Connection, Started) {
+        let (server, client) = UnixStream::pair().unwrap();
+        let started = Started::default();
+        let manager = Manager {
+            started: started.clone(),
+        };
+
+        // Both ends take part in the handshake, so the server can't be built on this thread
+        let server = std::thread::spawn(move || {
+            connection::Builder::unix_stream(server)
+                .server(zbus::Guid::generate())
+                .unwrap()
+                .p2p()
+                .serve_at("/org/freedesktop/systemd1", manager)
+                .unwrap()
+                .build()
+                .unwrap()
+        });
+        let client = connection::Builder::unix_stream(client)
+            .p2p()
+            .build()
+            .unwrap();
+
+        (ScopeLauncher::new(client), server.join().unwrap(), started)
+    }
+
+    #[test]
+    fn starts_a_scope_holding_the_pid() {
+        let (launcher, _server, started) = stub_launcher();
+
+        let unit = launcher
+            .move_to_scope("org.gnome.Nautilus.desktop", 4242)
+            .unwrap();
+
+        let random = unit
+            .strip_prefix("app-swift-org.gnome.Nautilus-")
+            .and_then(|rest| rest.strip_suffix(".scope"))
+            .unwrap_or_else(|| panic!("unexpected unit name {}", unit));
+        assert_eq!(random.len(), 8);
+        assert!(random.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let started = started.lock().unwrap();
+        assert_eq!(started.len(), 1);
+        let (name, properties) = &started[0];
+        assert_eq!(name, &unit);
+        let pids: Vec<u32> = properties["PIDs"].try_clone().unwrap().try_into().unwrap();
+        assert_eq!(pids, [4242]);
+    }
+
+    #[test]
+    fn escapes_unit_names() {
+        assert_eq!(escape("org.gnome.Nautilus"), "org.gnome.Nautilus");
+        assert_eq!(escape("my_app:2"), "my_app:2");
+        assert_eq!(escape("my-app"), r"my\x2dapp");
+        assert_eq!(escape(".hidden"), r"\x2ehidden");
+        assert_eq!(escape("a b/é"), r"a\x20b\x2f\xc3\xa9");
+    }
+}
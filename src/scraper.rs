@@ -1,22 +1,29 @@
-use ini::Ini;
-use regex::Regex;
+use ini::{Ini, ParseOption, Properties};
+use std::collections::HashSet;
 use std::env;
 use std::error::Error;
 use std::fs;
 // use std::option::Option;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use crate::ActionItem;
+use crate::exec::is_executable;
 
 pub fn get_programs() -> Vec<ActionItem> {
     let data_dirs = env::var("XDG_DATA_DIRS").unwrap_or_else(|_| {
         "/var/lib/flatpak/exports/share:/usr/local/share:/usr/share:/usr/share/gnome:/usr/share/plasma:/var/lib/snapd/desktop".to_string()
     });
-    let mut clean_dirs: Vec<&str> = data_dirs.split(":").collect();
-    clean_dirs.retain(|&s| !s.starts_with("/nix/store/"));
+    let data_home = env::var("XDG_DATA_HOME")
+        .unwrap_or_else(|_| format!("{}/.local/share", env::var("HOME").unwrap_or_default()));
+
+    // $XDG_DATA_HOME takes precedence over every system directory
+    let mut clean_dirs: Vec<&str> = vec![data_home.as_str()];
+    clean_dirs.extend(data_dirs.split(":"));
+    clean_dirs.retain(|&s| !s.is_empty() && !s.starts_with("/nix/store/"));
 
     let start = Instant::now();
+    let locales = get_locales();
 
     // println!("{:?}", clean_dirs);
 
@@ -46,25 +53,19 @@ pub fn get_programs() -> Vec<ActionItem> {
     //     }
     // }
 
-    let all_app_dirs: Vec<_> = clean_dirs
-        .iter()
-        .filter_map(|dir| {
-            let path = format!("{}/applications", dir);
-            fs::read_dir(&path).ok()
-        })
-        .flatten()
-        .filter_map(|entry| entry.ok())
-        .map(|entry| entry.path())
-        .collect();
+    // The first file found for each desktop-file ID wins, even if it turns out hidden,
+    // so user overrides can replace or mask system entries
+    let mut seen = HashSet::new();
+    let mut desktop_files = Vec::new();
+    for dir in &clean_dirs {
+        let base = PathBuf::from(format!("{}/applications", dir));
+        collect_desktop_files(&base, &base, &mut seen, &mut desktop_files);
+    }
 
-    let items: Vec<ActionItem> = all_app_dirs
+    let items: Vec<ActionItem> = desktop_files
         .into_iter()
-        .filter_map(|path| {
-            fs::metadata(&path)
-                .ok()
-                .filter(|meta| meta.is_file())
-                .and_then(|_| get_desktop_data(&path).ok())
-        })
+        .filter_map(|(id, path)| get_desktop_data(&path, &id, &locales).ok())
+        .flatten()
         .collect();
 
     println!(
@@ -75,36 +76,135 @@ pub fn get_programs() -> Vec<ActionItem> {
     items
 }
 
-fn get_desktop_data(path: &Path) -> Result<ActionItem, Box<dyn Error>> {
+fn collect_desktop_files(
+    base: &Path,
+    dir: &Path,
+    seen: &mut HashSet<String>,
+    files: &mut Vec<(String, PathBuf)>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect_desktop_files(base, &path, seen, files);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("desktop")
+            && let Some(id) = desktop_file_id(base, &path)
+            && seen.insert(id.clone())
+        {
+            files.push((id, path));
+        }
+    }
+}
+
+/// Path relative to the `applications` directory with `/` replaced by `-`,
+/// e.g. `applications/kde/foo.desktop` is `kde-foo.desktop`.
+fn desktop_file_id(base: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("-"))
+}
+
+/// Returns the application itself followed by one item per `[Desktop Action]` it declares.
+fn get_desktop_data(
+    path: &Path,
+    id: &str,
+    locales: &[String],
+) -> Result<Vec<ActionItem>, Box<dyn Error>> {
     // let desktop_file = Ini::load_from_file(&path).unwrap();
     // println!("Getting .desktop data");
 
-    if let Ok(conf) = Ini::load_from_file(path) {
+    // Quotes and backslashes belong to the Exec quoting rules, not to the ini parser
+    let opt = ParseOption {
+        enabled_quote: false,
+        enabled_escape: false,
+        ..Default::default()
+    };
+
+    if let Ok(conf) = Ini::load_from_file_opt(path, opt) {
         match conf.section(Some("Desktop Entry")) {
             Some(section) => {
-                let desktop_name = section.get("Name").unwrap_or("");
+                let desktop_name = get_localized(section, "Name", locales).unwrap_or("");
                 let desktop_command = section.get("Exec").unwrap_or("");
-                let desktop_keywords = section.get("Keywords").unwrap_or("");
+                let desktop_keywords = get_localized(section, "Keywords", locales).unwrap_or("");
                 let desktop_type = section.get("Type").unwrap_or("");
 
-                if desktop_type == "Application" {
-                    // println!(
-                    //     "desktop app found! Name: {} -- Exec: {}",
-                    //     desktop_name, desktop_command
-                    // );
-
-                    let desktop_command = strip_field_codes_regex(desktop_command);
-
-                    Ok(ActionItem {
-                        name: desktop_name.into(),
-                        exec: desktop_command.into(),
-                        keywords: desktop_keywords.into(),
-                        icon: Default::default(),
-                    })
-                } else {
+                if desktop_type != "Application" {
                     println!("Desktop entry doesnt have type or isnt type application");
-                    Err("Desktop entry doesnt have type or isnt type application".into())
+                    return Err("Desktop entry doesnt have type or isnt type application".into());
+                }
+
+                if get_bool(section.get("Hidden")) || get_bool(section.get("NoDisplay")) {
+                    return Err("Desktop entry is hidden".into());
+                }
+
+                if let Some(try_exec) = section.get("TryExec")
+                    && !is_executable(try_exec)
+                {
+                    return Err(format!("TryExec {} not found", try_exec).into());
+                }
+
+                if !shown_in_current_desktop(section.get("OnlyShowIn"), section.get("NotShowIn")) {
+                    return Err("Desktop entry not shown in this desktop".into());
+                }
+
+                // println!(
+                //     "desktop app found! Name: {} -- Exec: {}",
+                //     desktop_name, desktop_command
+                // );
+
+                let app = ActionItem {
+                    name: desktop_name.into(),
+                    exec: desktop_command.into(),
+                    keywords: desktop_keywords.into(),
+                    untranslated_name: section.get("Name").unwrap_or("").into(),
+                    generic_name: get_localized(section, "GenericName", locales)
+                        .unwrap_or("")
+                        .into(),
+                    comment: get_localized(section, "Comment", locales).unwrap_or("").into(),
+                    categories: section.get("Categories").unwrap_or("").into(),
+                    path: section.get("Path").unwrap_or("").into(),
+                    terminal: get_bool(section.get("Terminal")),
+                    desktop_id: id.into(),
+                    icon_name: section.get("Icon").unwrap_or("").into(),
+                    file: path.to_string_lossy().as_ref().into(),
+                    ..Default::default()
+                };
+
+                let mut items = Vec::new();
+                for action in get_list(section.get("Actions").unwrap_or("")) {
+                    let Some(group) = conf.section(Some(format!("Desktop Action {}", action)))
+                    else {
+                        continue;
+                    };
+                    let (Some(action_name), Some(action_exec)) =
+                        (get_localized(group, "Name", locales), group.get("Exec"))
+                    else {
+                        continue;
+                    };
+
+                    items.push(ActionItem {
+                        name: format!("{}: {}", desktop_name, action_name).into(),
+                        exec: action_exec.into(),
+                        untranslated_name: format!(
+                            "{}: {}",
+                            section.get("Name").unwrap_or(""),
+                            group.get("Name").unwrap_or("")
+                        )
+                        .into(),
+                        ..app.clone()
+                    });
                 }
+                items.insert(0, app);
+
+                Ok(items)
             }
             None => {
                 println!("No Desktop entry");
@@ -118,12 +218,75 @@ fn get_desktop_data(path: &Path) -> Result<ActionItem, Box<dyn Error>> {
     // let section = desktop_file.section(Some("Desktop Entry"))?;
 }
 
-fn strip_field_codes_regex(exec: &str) -> String {
-    let re_exec = Regex::new(r"@@.*@@").unwrap();
-    let result = re_exec.replace_all(exec, "");
+/// Looks up `key[locale]` following the Desktop Entry matching order, falling back to plain `key`.
+fn get_localized<'a>(section: &'a Properties, key: &str, locales: &[String]) -> Option<&'a str> {
+    locales
+        .iter()
+        .find_map(|locale| section.get(format!("{}[{}]", key, locale)))
+        .or_else(|| section.get(key))
+}
+
+/// Candidate locale suffixes for the current messages locale, most specific first.
+/// `de_DE.UTF-8@euro` gives `de_DE@euro`, `de_DE`, `de@euro`, `de`.
+fn get_locales() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale.as_str(), None),
+    };
+    // The encoding is never part of the key
+    let locale = locale.split('.').next().unwrap_or("");
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+
+    let mut locales = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        locales.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        locales.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        locales.push(format!("{}@{}", lang, modifier));
+    }
+    locales.push(lang.to_string());
+    locales
+}
+
+fn get_bool(value: Option<&str>) -> bool {
+    value.is_some_and(|v| v.trim() == "true")
+}
+
+fn get_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(';').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn shown_in_current_desktop(only_show_in: Option<&str>, not_show_in: Option<&str>) -> bool {
+    let current = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let current: Vec<&str> = current.split(':').filter(|s| !s.is_empty()).collect();
 
-    let re_xdg = Regex::new(r"%[fFuUdDnNickvm]").unwrap();
-    let result = re_xdg.replace_all(&result, "");
+    if let Some(only) = only_show_in
+        && !get_list(only).any(|desktop| current.contains(&desktop))
+    {
+        return false;
+    }
+
+    if let Some(not) = not_show_in
+        && get_list(not).any(|desktop| current.contains(&desktop))
+    {
+        return false;
+    }
 
-    result.split_whitespace().collect::<Vec<_>>().join(" ")
+    true
 }
@@ -0,0 +1,47 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where every plugin finds its own writable directory
+pub const GUEST_PATH: &str = "/data";
+
+/// `$XDG_DATA_HOME/swift/plugin-data/<id>`, kept across runs and reloads.
+pub fn data_dir(id: &str) -> PathBuf {
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .unwrap_or_else(|_| format!("{}/.local/share", std::env::var("HOME").unwrap_or_default()));
+    PathBuf::from(format!("{}/swift/plugin-data/{}", data_home, id))
+}
+
+/// Bytes used by everything below `dir`, a missing directory uses nothing.
+pub fn usage(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => usage(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Deletes everything plugin `id` stored, it starts over with an empty directory.
+pub fn wipe(id: &str) -> Result<(), Box<dyn Error>> {
+    if id.is_empty() || id.contains('/') || id == "." || id == ".." {
+        return Err(format!("Invalid plugin id {:?}", id).into());
+    }
+
+    let dir = data_dir(id);
+    if !dir.exists() {
+        println!("{} has no stored data", id);
+        return Ok(());
+    }
+
+    let freed = usage(&dir);
+    fs::remove_dir_all(&dir)?;
+    println!("Wiped {} ({} KiB)", dir.display(), freed.div_ceil(1024));
+    Ok(())
+}